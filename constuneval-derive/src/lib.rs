@@ -0,0 +1,349 @@
+//! Derive macro for [constuneval](https://docs.rs/constuneval)'s `Uneval` trait.
+//!
+//! Use it through the `derive` feature of `constuneval` rather than depending on this
+//! crate directly:
+//! ```ignore
+//! use constuneval::Uneval;
+//!
+//! #[derive(Uneval)]
+//! struct Point {
+//!     x: i32,
+//!     y: i32,
+//! }
+//! ```
+//!
+//! ## Attributes
+//! - `#[uneval(path = "some::Path")]` on a struct or enum: path emitted for the type
+//!   instead of its bare name. Enum variants are emitted as `some::Path::Variant`.
+//!   Without it, the type is registered with `Emitter::import_type` so that
+//!   `Config::module` imports it, and with it, a relative path is registered with
+//!   `Emitter::import_relative`.
+//!
+//!   The path is also the one of the inferred item type, `some::Path<'static, T, N>` for
+//!   generic types, lifetimes being `'static` and type parameters being inferred by their
+//!   own `Uneval` implementation.
+//! - `#[uneval(skip = "expr")]` on a field: emit `expr` instead of the field value, e.g.
+//!   `#[uneval(skip = "Vec::new()")]` for a cache.
+//! - `#[uneval(skip)]` on a named field of a type with `#[uneval(path = "...")]`: don't
+//!   emit the field at all, the path being a type without this field.
+//! - `#[uneval(rename = "name")]` on a named field: field name used in the emitted
+//!   struct expression.
+//! - `#[uneval(with = "some::function")]` on a field: emit the field with
+//!   `fn(&FieldType, &mut Emitter<'_>) -> constuneval::Result<()>` instead of its `Uneval`
+//!   implementation.
+//! - `#[uneval(owned)]` on a `Vec` or `String` field: emit it as `Vec::new()` or
+//!   `String::new()` with `constuneval::Owned` instead of a slice or `&str`, failing if it
+//!   isn't empty.
+//!
+//! `Vec`, `String`, map and set values are emitted as `&[...]` slices and `"..."`
+//! literals, which don't have their type. Without `#[uneval(path = "...")]` naming a type
+//! with `&'static` fields instead, fields containing them, e.g. `Option<Vec<u8>>`, are
+//! rejected unless they have one of the field attributes above. Aliases of these types
+//! can't be seen by the derive, and fail to compile where the code is included.
+//!
+//! Empty `Vec` and `String` fields are only emitted as `Vec::new()` and `String::new()`
+//! with `#[uneval(owned)]`: non-empty ones can't be built in const context, so making it
+//! the default would turn a compile error of the derive into an error while generating
+//! the code, depending on the values.
+
+extern crate proc_macro;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Error, Fields, GenericArgument,
+    GenericParam, Generics, Index, LitStr, Member, Path, PathArguments, Type, TypePath,
+};
+
+/// Derive `constuneval::Uneval`, see the [crate] documentation for attributes.
+#[proc_macro_derive(Uneval, attributes(uneval))]
+pub fn derive_uneval(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct ContainerAttrs {
+    path: Option<String>,
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    /// `Some(None)` for `skip`, `Some(Some(expr))` for `skip = "expr"`.
+    skip: Option<Option<String>>,
+    owned: bool,
+    rename: Option<String>,
+    with: Option<Path>,
+}
+
+fn container_attrs(attrs: &[Attribute]) -> syn::Result<ContainerAttrs> {
+    let mut res = ContainerAttrs::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("uneval")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("path") {
+                res.path = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else {
+                Err(meta.error("unknown uneval container attribute"))
+            }
+        })?;
+    }
+    Ok(res)
+}
+
+fn field_attrs(attrs: &[Attribute]) -> syn::Result<FieldAttrs> {
+    let mut res = FieldAttrs::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("uneval")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                res.skip = Some(if meta.input.peek(syn::Token![=]) {
+                    Some(meta.value()?.parse::<LitStr>()?.value())
+                } else {
+                    None
+                });
+                Ok(())
+            } else if meta.path.is_ident("owned") {
+                res.owned = true;
+                Ok(())
+            } else if meta.path.is_ident("rename") {
+                res.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else if meta.path.is_ident("with") {
+                res.with = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unknown uneval field attribute"))
+            }
+        })?;
+    }
+    Ok(res)
+}
+
+fn expand(mut input: DeriveInput) -> syn::Result<TokenStream> {
+    let attrs = container_attrs(&input.attrs)?;
+    // types are imported by `Config::module`, by their bare name or their relative path
+    let import = match &attrs.path {
+        Some(path) => quote!(e.import_relative(#path);),
+        None => quote!(e.import_type::<Self>();),
+    };
+    let has_path = attrs.path.is_some();
+    let path = attrs.path.unwrap_or_else(|| input.ident.to_string());
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let (pattern, emit) = fields(&path, has_path, &data.fields)?;
+            quote! {
+                let Self #pattern = self;
+                #emit
+            }
+        }
+        Data::Enum(data) if data.variants.is_empty() => quote!(match *self {}),
+        Data::Enum(data) => {
+            let arms = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    let ident = &variant.ident;
+                    let variant_path = format!("{}::{}", path, ident);
+                    let (pattern, emit) = fields(&variant_path, has_path, &variant.fields)?;
+                    Ok(quote!(Self::#ident #pattern => { #emit }))
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(_) => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "Uneval can't be derived for unions",
+            ))
+        }
+    };
+
+    let const_type = const_type(&path, &input.generics);
+    for param in input.generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::constuneval::Uneval));
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::constuneval::Uneval for #ident #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn uneval(&self, e: &mut ::constuneval::Emitter<'_>) -> ::constuneval::Result<()> {
+                #import
+                #body
+            }
+
+            fn const_type() -> ::constuneval::Result<::std::string::String> {
+                #const_type
+            }
+        }
+    })
+}
+
+/// Code building the type of the emitted expression, lifetimes being replaced by
+/// `'static`.
+fn const_type(path: &str, generics: &Generics) -> TokenStream {
+    if generics.params.is_empty() {
+        return quote!(::core::result::Result::Ok(::std::string::String::from(#path)));
+    }
+    let args = generics.params.iter().map(|param| match param {
+        GenericParam::Lifetime(_) => quote!(::std::string::String::from("'static")),
+        GenericParam::Type(param) => {
+            let ident = &param.ident;
+            quote!(<#ident as ::constuneval::Uneval>::const_type()?)
+        }
+        GenericParam::Const(param) => {
+            let ident = &param.ident;
+            quote!(::std::string::ToString::to_string(&#ident))
+        }
+    });
+    quote! {
+        let args: &[::std::string::String] = &[#(#args),*];
+        ::core::result::Result::Ok(::std::format!("{}<{}>", #path, args.join(", ")))
+    }
+}
+
+/// Types whose values can't be built in const context, emitted as slices and `&str`, or
+/// not emitted at all for `Box`.
+const BORROWED_TYPES: &[&str] = &[
+    "Vec", "String", "Box", "HashMap", "BTreeMap", "HashSet", "BTreeSet",
+];
+
+/// First type of [BORROWED_TYPES] in `ty`, including its generic arguments, tuples and
+/// arrays, by the last segment of its path. Aliases can't be seen, and `Owned` is emitted
+/// with its type.
+fn borrowed_type(ty: &Type) -> Option<&TypePath> {
+    match ty {
+        Type::Path(path) => {
+            let last = path.path.segments.last()?;
+            if BORROWED_TYPES.iter().any(|name| last.ident == name) {
+                return Some(path);
+            }
+            if last.ident == "Owned" {
+                return None;
+            }
+            let args = match &last.arguments {
+                PathArguments::AngleBracketed(args) => &args.args,
+                _ => return None,
+            };
+            args.iter().find_map(|arg| match arg {
+                GenericArgument::Type(ty) => borrowed_type(ty),
+                _ => None,
+            })
+        }
+        Type::Array(array) => borrowed_type(&array.elem),
+        Type::Slice(slice) => borrowed_type(&slice.elem),
+        Type::Reference(reference) => borrowed_type(&reference.elem),
+        Type::Tuple(tuple) => tuple.elems.iter().find_map(borrowed_type),
+        Type::Paren(paren) => borrowed_type(&paren.elem),
+        Type::Group(group) => borrowed_type(&group.elem),
+        _ => None,
+    }
+}
+
+/// Destructuring pattern binding every field, and the code emitting them.
+///
+/// Fields can only be left out with `skip` when `has_path`, as the emitted type must not
+/// have them.
+fn fields(path: &str, has_path: bool, fields: &Fields) -> syn::Result<(TokenStream, TokenStream)> {
+    let mut bindings = Vec::new();
+    let mut emits = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let attrs = field_attrs(&field.attrs)?;
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(i)),
+        };
+        let binding = format_ident!("__field{}", i);
+        bindings.push(quote!(#member: #binding));
+        let value = match (&attrs.skip, &attrs.with) {
+            (Some(_), _) if attrs.with.is_some() || attrs.owned => {
+                return Err(Error::new_spanned(
+                    field,
+                    "`skip` can't be used with `with` or `owned`",
+                ))
+            }
+            (Some(None), _) if field.ident.is_none() => {
+                return Err(Error::new_spanned(
+                    field,
+                    "`skip` would shift the following fields of a tuple struct, \
+                     use `skip = \"expr\"` to emit `expr` instead",
+                ))
+            }
+            (Some(None), _) if !has_path => {
+                return Err(Error::new_spanned(
+                    field,
+                    "`skip` leaves the field out of the emitted struct, which needs \
+                     `#[uneval(path = \"...\")]` on the type or `skip = \"expr\"`",
+                ))
+            }
+            (Some(None), _) => continue,
+            (Some(Some(expr)), _) => quote! {
+                &::constuneval::__private::UnevalFn(|e: &mut ::constuneval::Emitter<'_>| {
+                    ::core::result::Result::Ok(::core::fmt::Write::write_str(e, #expr)?)
+                })
+            },
+            (None, Some(_)) if attrs.owned => {
+                return Err(Error::new_spanned(
+                    field,
+                    "`with` and `owned` can't be used together",
+                ))
+            }
+            (None, Some(with)) => quote! {
+                &::constuneval::__private::UnevalFn(|e: &mut ::constuneval::Emitter<'_>| {
+                    #with(#binding, e)
+                })
+            },
+            (None, None) if attrs.owned => quote!(&::constuneval::Owned(#binding)),
+            (None, None) => match borrowed_type(&field.ty) {
+                Some(ty) if !has_path => {
+                    let name = &ty.path.segments.last().unwrap().ident;
+                    return Err(Error::new_spanned(
+                        ty,
+                        format!(
+                            "`{}` values can't be built in const context, use \
+                             `#[uneval(owned)]` for an empty `Vec` or `String` field, \
+                             `#[uneval(with = \"...\")]`, or `#[uneval(path = \"...\")]` on \
+                             the type naming one with `&'static` fields",
+                            name
+                        ),
+                    ));
+                }
+                _ => quote!(#binding),
+            },
+        };
+        emits.push(match &field.ident {
+            Some(ident) => {
+                let name = attrs.rename.unwrap_or_else(|| ident.to_string());
+                quote!(.field(#name, #value))
+            }
+            None => {
+                if attrs.rename.is_some() {
+                    return Err(Error::new_spanned(
+                        field,
+                        "`rename` is only supported on named fields",
+                    ));
+                }
+                quote!(.field(#value))
+            }
+        });
+    }
+
+    let pattern = quote!({ #(#bindings,)* .. });
+    let emit = match fields {
+        Fields::Named(_) => quote!(e.struct_expr(#path) #(#emits)* .finish()),
+        Fields::Unnamed(_) => quote!(e.tuple_expr(#path) #(#emits)* .finish()),
+        Fields::Unit => quote! {
+            ::core::fmt::Write::write_str(e, #path)?;
+            ::core::result::Result::Ok(())
+        },
+    };
+    Ok((pattern, emit))
+}
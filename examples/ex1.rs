@@ -1,18 +1,26 @@
 extern crate constuneval;
 
-use constuneval::{to_string, UnevalCow};
-use std::fmt;
+use constuneval::{to_string, Emitter, Result, Uneval, UnevalCow};
 
-#[derive(Debug)]
 pub struct FftDomain<F>
 where
     // F: 'static,
-    [F]: 'static + ToOwned + fmt::Debug,
-    <[F] as std::borrow::ToOwned>::Owned: fmt::Debug,
+    [F]: 'static + ToOwned,
 {
     pub some_table: UnevalCow<'static, [UnevalCow<'static, [F]>]>,
 }
 
+impl<F: Uneval> Uneval for FftDomain<F>
+where
+    [F]: 'static + ToOwned,
+{
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        e.struct_expr("FftDomain")
+            .field("some_table", &self.some_table)
+            .finish()
+    }
+}
+
 fn main() {
     let fft_temp = FftDomain {
         some_table: UnevalCow::Owned(vec![
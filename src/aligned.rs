@@ -0,0 +1,66 @@
+//! Bytes aligned for zero-copy reinterpretation.
+
+use std::fmt::{self, Write};
+use std::ops::Deref;
+
+use crate::{Emitter, Result, Uneval};
+
+/// Bytes stored with the alignment of `A`, so they can be reinterpreted as `A` values
+/// without copying, e.g. with `bytemuck`.
+///
+/// `include_bytes!` and byte strings only guarantee an alignment of 1. Wrapping the data
+/// with `Aligned::<u64, _>::new(bytes)` in the build script emits
+/// `&::constuneval::Aligned::new([...])`, a `&'static Aligned<u64, [u8]>` whose bytes
+/// start at an address aligned for `u64`.
+///
+/// ```
+/// use constuneval::Aligned;
+///
+/// static DATA: &Aligned<u32, [u8]> = &Aligned::new([1, 0, 0, 0, 2, 0, 0, 0]);
+/// assert_eq!(DATA.as_ptr() as usize % std::mem::align_of::<u32>(), 0);
+/// assert_eq!(DATA.len(), 8);
+/// ```
+#[repr(C)]
+pub struct Aligned<A, B: ?Sized> {
+    align: [A; 0],
+    bytes: B,
+}
+
+impl<A, B> Aligned<A, B> {
+    /// Wrap `bytes`.
+    pub const fn new(bytes: B) -> Self {
+        Aligned { align: [], bytes }
+    }
+}
+
+impl<A, B: ?Sized> Deref for Aligned<A, B> {
+    type Target = B;
+
+    fn deref(&self) -> &B {
+        &self.bytes
+    }
+}
+
+impl<A, B: ?Sized + fmt::Debug> fmt::Debug for Aligned<A, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Aligned").field(&&self.bytes).finish()
+    }
+}
+
+impl<A: Uneval, B: AsRef<[u8]>> Uneval for Aligned<A, B> {
+    /// Emits `&Aligned::new(bytes)`, `bytes` being emitted as an array, a dereferenced
+    /// byte string or a dereferenced `include_bytes!`, as with [Emitter::emit_bytes].
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        let path = e.crate_path("Aligned");
+        write!(e, "&{}::new(", path)?;
+        e.emit_byte_array(self.bytes.as_ref())?;
+        Ok(e.write_str(")")?)
+    }
+
+    fn const_type() -> Result<String> {
+        Ok(format!(
+            "&'static ::constuneval::Aligned<{}, [u8]>",
+            A::const_type()?
+        ))
+    }
+}
@@ -0,0 +1,105 @@
+//! Integration with Cargo build scripts.
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+use crate::{Error, Result};
+
+/// Variables set by Cargo when running a build script.
+///
+/// `OUT_DIR` alone isn't enough, as Cargo also sets it for the tests and binaries of a
+/// package with a build script.
+const BUILD_SCRIPT_VARS: [&str; 4] = ["OUT_DIR", "TARGET", "HOST", "NUM_JOBS"];
+
+/// Whether the current process is a build script, i.e. `OUT_DIR`, `TARGET`, `HOST` and
+/// `NUM_JOBS` are set.
+pub fn is_build_script() -> bool {
+    BUILD_SCRIPT_VARS
+        .iter()
+        .all(|var| env::var_os(var).is_some())
+}
+
+/// Tell Cargo to rerun the build script when the file or directory at `path` changes.
+///
+/// Prints `cargo:rerun-if-changed=PATH` when called from a build script, does nothing
+/// otherwise.
+pub fn rerun_if_changed(path: impl AsRef<Path>) {
+    if is_build_script() {
+        println!("cargo:rerun-if-changed={}", path.as_ref().display());
+    }
+}
+
+/// Tell Cargo to rerun the build script when the environment variable `var` changes, and
+/// return its value.
+///
+/// Prints `cargo:rerun-if-env-changed=VAR` when called from a build script.
+///
+/// ```no_run
+/// let size: usize = constuneval::rerun_if_env_changed("TABLE_SIZE")
+///     .map_or(256, |size| size.parse().expect("Invalid TABLE_SIZE"));
+/// ```
+pub fn rerun_if_env_changed(var: &str) -> Option<String> {
+    if is_build_script() {
+        println!("cargo:rerun-if-env-changed={}", var);
+    }
+    env::var(var).ok()
+}
+
+/// Display `message` as a warning of the build script.
+///
+/// Prints `cargo:warning=MESSAGE` for every line of `message` when called from a build
+/// script, writes it to stderr otherwise.
+pub fn warning(message: &str) {
+    if is_build_script() {
+        for line in message.lines() {
+            println!("cargo:warning={}", line);
+        }
+    } else {
+        eprintln!("warning: {}", message);
+    }
+}
+
+/// Path of `file_name` inside the `OUT_DIR` of the running build script.
+///
+/// Fails with [Error::NotBuildScript] when not called from a build script, see
+/// [is_build_script()].
+///
+/// ```no_run
+/// use constuneval::{out_path, ConstWriter};
+///
+/// ConstWriter::new(out_path("tables.rs")?)
+///     .item("ONE", &1_u8, Some("u8"))
+///     .write()?;
+/// # Ok::<(), constuneval::Error>(())
+/// ```
+pub fn out_path(file_name: &str) -> Result<PathBuf> {
+    match env::var_os("OUT_DIR") {
+        Some(dir) if is_build_script() => Ok(PathBuf::from(dir).join(file_name)),
+        _ => Err(Error::NotBuildScript),
+    }
+}
+
+/// Include a file generated in `OUT_DIR` by the build script.
+///
+/// `include_generated!("file_name.rs")` is a shorthand for
+/// `include!(concat!(env!("OUT_DIR"), "/file_name.rs"))`, failing with a clear message
+/// when the crate has no build script.
+///
+/// ```ignore
+/// constuneval::include_generated!("tables.rs");
+/// ```
+#[macro_export]
+macro_rules! include_generated {
+    ($file_name:literal) => {
+        ::core::include! {
+            ::core::concat!(
+                ::core::env!(
+                    "OUT_DIR",
+                    "`OUT_DIR` is not set, `include_generated!` needs a build script"
+                ),
+                "/",
+                $file_name
+            )
+        }
+    };
+}
@@ -0,0 +1,940 @@
+//! Options controlling the generated items.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Write};
+use std::io;
+use std::path::Path;
+
+use crate::file::{write_file_with, IoWriter, ScratchFile, Sidecars};
+use crate::{Emitter, Error, Header, Result, Uneval, WriteMode};
+
+/// Path of constuneval in the emitted code, unless set by [Config::crate_path].
+const DEFAULT_CRATE_PATH: &str = "::constuneval";
+/// Length from which byte slices are emitted as byte strings by default.
+pub(crate) const DEFAULT_BYTE_STRINGS: usize = 16;
+
+/// Kind of the generated item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ItemKind {
+    /// `const NAME: TY = ...;`, inlined at every use site.
+    #[default]
+    Const,
+    /// `static NAME: TY = ...;`, which lives at a single address. Prefer it for big
+    /// lookup tables so they are not duplicated in the binary.
+    Static,
+}
+
+impl fmt::Display for ItemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ItemKind::Const => "const",
+            ItemKind::Static => "static",
+        })
+    }
+}
+
+/// Visibility of the generated item.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Visibility {
+    /// No visibility qualifier.
+    #[default]
+    Private,
+    /// `pub`
+    Public,
+    /// `pub(crate)`
+    Crate,
+    /// `pub(super)`
+    Super,
+    /// `pub(in path)`, built with [Visibility::restricted()].
+    Restricted(RestrictedPath),
+}
+
+/// Path of a [Visibility::Restricted], checked to start with `crate`, `self` or `super`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RestrictedPath(Cow<'static, str>);
+
+impl RestrictedPath {
+    /// The path, e.g. `crate::tables`.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Visibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Visibility::Private => Ok(()),
+            Visibility::Public => f.write_str("pub "),
+            Visibility::Crate => f.write_str("pub(crate) "),
+            Visibility::Super => f.write_str("pub(super) "),
+            Visibility::Restricted(path) => write!(f, "pub(in {}) ", path.as_str()),
+        }
+    }
+}
+
+impl Visibility {
+    /// `pub(in path)`, failing with [Error::InvalidVisibility] unless `path` is a module
+    /// path starting with `crate`, `self` or `super`.
+    ///
+    /// ```
+    /// use constuneval::{Config, Visibility};
+    ///
+    /// let tables = String::from("crate::tables");
+    /// let config = Config::new().vis(Visibility::restricted(tables)?);
+    /// assert_eq!(
+    ///     config.to_string("A", &1_u8, None),
+    ///     "pub(in crate::tables) const A: u8 = 1;"
+    /// );
+    /// assert!(Visibility::restricted("tables").is_err());
+    /// # Ok::<(), constuneval::Error>(())
+    /// ```
+    pub fn restricted(path: impl Into<Cow<'static, str>>) -> Result<Self> {
+        let path = path.into();
+        let mut segments = path.split("::");
+        let first = segments.next().unwrap_or_default();
+        // `super` can only follow `self` and `super`
+        let mut relative = first == "self" || first == "super";
+        let valid = (relative || first == "crate")
+            && segments.all(|segment| {
+                relative &= segment == "super";
+                relative || is_ident(segment) && segment != "_"
+            });
+        if valid {
+            Ok(Visibility::Restricted(RestrictedPath(path)))
+        } else {
+            Err(Error::InvalidVisibility(path.into_owned()))
+        }
+    }
+
+    /// Visibility giving the same access from inside a module, see [Config::module].
+    ///
+    /// Paths relative to the module are relative to its parent outside of it, so they
+    /// get one more `super`.
+    fn in_module(&self) -> String {
+        match self {
+            Visibility::Private => Visibility::Super.to_string(),
+            Visibility::Super => "pub(in super::super) ".to_string(),
+            Visibility::Restricted(path) => match path.as_str() {
+                "self" => Visibility::Super.to_string(),
+                path => match path.strip_prefix("self::") {
+                    Some(path) => format!("pub(in super::{}) ", path),
+                    None if path == "super" || path.starts_with("super::") => {
+                        format!("pub(in super::{}) ", path)
+                    }
+                    None => self.to_string(),
+                },
+            },
+            vis => vis.to_string(),
+        }
+    }
+}
+
+/// Radix of the emitted integer literals.
+///
+/// Literals other than decimal ones are padded with zeros to the width of their type,
+/// except for `usize` and `isize` whose width depends on the target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Radix {
+    /// `255`
+    #[default]
+    Decimal,
+    /// `0xff`, e.g. for S-boxes and CRC tables.
+    Hex,
+    /// `0o377`
+    Octal,
+    /// `0b11111111`, e.g. for bitmasks.
+    Binary,
+}
+
+/// Layout of the generated expressions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Style {
+    /// Every field and element on its own line, as `{:#?}` does.
+    #[default]
+    Pretty,
+    /// Everything on a single line, e.g. `Point { x: 1, y: 2 }` and `[1, 2, 3]`.
+    Compact,
+    /// [Pretty][Style::Pretty], except that array elements are written compactly, `n`
+    /// per line.
+    Packed(usize),
+    /// [Pretty][Style::Pretty], except that array elements are written compactly, as many
+    /// as possible per line without exceeding the given width.
+    Wrapped(usize),
+}
+
+/// Configuration of the generated code.
+///
+/// [to_string()][crate::to_string] and [to_file()][crate::to_file] use the default
+/// configuration, emitting private `const` items.
+///
+/// ```
+/// use constuneval::{Config, ItemKind, Visibility};
+///
+/// let config = Config::new().kind(ItemKind::Static).vis(Visibility::Public);
+/// assert_eq!(
+///     config.to_string("TABLE", &[1_u8], Some("[u8; 1]")),
+///     "pub static TABLE: [u8; 1] = [\n    1,\n];"
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    kind: ItemKind,
+    vis: Visibility,
+    write_mode: WriteMode,
+    pub(crate) float_bits: bool,
+    pub(crate) suffixes: bool,
+    pub(crate) radix: Radix,
+    pub(crate) digit_group: usize,
+    pub(crate) sidecar_threshold: Option<usize>,
+    pub(crate) byte_strings: Option<usize>,
+    pub(crate) style: Style,
+    header: Header,
+    module: Option<String>,
+    crate_path: Option<String>,
+    cow_path: Option<String>,
+    docs: Vec<String>,
+    attrs: Vec<String>,
+}
+
+impl Config {
+    /// Create the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the kind of the generated item, `const` by default.
+    pub fn kind(mut self, kind: ItemKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Set the visibility of the generated item, private by default.
+    pub fn vis(mut self, vis: Visibility) -> Self {
+        self.vis = vis;
+        self
+    }
+
+    /// Add a line of documentation to the generated item, written as `/// line`.
+    ///
+    /// `doc` may contain many lines.
+    pub fn doc(mut self, doc: &str) -> Self {
+        self.docs.extend(doc.lines().map(str::to_string));
+        self
+    }
+
+    /// Add an outer attribute to the generated item, `attr("allow(clippy::approx_constant)")`
+    /// writing `#[allow(clippy::approx_constant)]`.
+    ///
+    /// ```
+    /// use constuneval::{Config, Visibility};
+    ///
+    /// let config = Config::new()
+    ///     .vis(Visibility::Crate)
+    ///     .doc("Approximation of pi.")
+    ///     .attr("allow(clippy::approx_constant)");
+    /// assert_eq!(
+    ///     config.to_string("PI", &3.14_f32, None),
+    ///     "/// Approximation of pi.\n#[allow(clippy::approx_constant)]\n\
+    ///      pub(crate) const PI: f32 = 3.14;"
+    /// );
+    /// ```
+    pub fn attr(mut self, attr: &str) -> Self {
+        self.attrs.push(attr.to_string());
+        self
+    }
+
+    /// Set how [to_file()][Config::to_file] writes the file, see [WriteMode].
+    pub fn write_mode(mut self, mode: WriteMode) -> Self {
+        self.write_mode = mode;
+        self
+    }
+
+    /// Set the header of the files written by [to_file()][Config::to_file], see [Header].
+    pub fn header(mut self, header: Header) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Wrap the generated item in `pub mod name { ... }`, with `use` statements importing
+    /// the types it refers to by name.
+    ///
+    /// This way, the code compiles wherever it's included, without importing anything.
+    /// The types are the ones registered with [Emitter::import] while emitting the value,
+    /// which is done for [ViaDebug][crate::ViaDebug], derived implementations and the
+    /// relative paths set with [crate_path()][Config::crate_path] and
+    /// [cow_path()][Config::cow_path].
+    ///
+    /// The types named in the type of the value, generic arguments included, are imported
+    /// too, e.g. `Entry` for an empty `Vec<Entry>`. The ones of an explicit `ty` which
+    /// the value's type doesn't name, e.g. `Some("Entry")` for a [ViaDebug][crate::ViaDebug]
+    /// of another type, aren't: give them by full path, e.g.
+    /// `Some("&'static [crate::tables::Entry]")`, or import them with
+    /// [ConstWriter::use_item][crate::ConstWriter::use_item]. Two types with the same name
+    /// can't both be imported, which fails with [Error::Unsupported][crate::Error::Unsupported].
+    ///
+    /// The `use` statements follow the item, so that it's still streamed to files, and
+    /// the visibility of the item is adjusted to give the same access as without the
+    /// module, private items being `pub(super)`. See [ConstWriter::module] for many items.
+    ///
+    /// ```
+    /// use constuneval::{Config, ConstMap, Visibility};
+    ///
+    /// let entries = [(1_u8, 'a')];
+    /// let config = Config::new()
+    ///     .module("tables")
+    ///     .vis(Visibility::Public)
+    ///     .crate_path("deps");
+    /// assert_eq!(
+    ///     config.to_string("MAP", &ConstMap::new(&entries), None),
+    ///     "pub mod tables {
+    /// pub const MAP: deps::ConstMap<'static, u8, char> = deps::ConstMap::new(
+    ///     &[
+    ///         (
+    ///             1,
+    ///             'a',
+    ///         ),
+    ///     ],
+    /// );
+    /// use super::deps;
+    /// }"
+    /// );
+    /// ```
+    ///
+    /// [ConstWriter::module]: crate::ConstWriter::module
+    pub fn module(mut self, name: &str) -> Self {
+        self.module = Some(name.to_string());
+        self
+    }
+
+    /// Set the path of constuneval in the emitted code, `::constuneval` by default.
+    ///
+    /// The types of the crate, such as [ConstMap][crate::ConstMap] or
+    /// [UnevalCow][crate::UnevalCow], are referred to by their full path, so that the code
+    /// compiles without imports. Use it when constuneval isn't a dependency of the crate
+    /// including the code, e.g. `crate::deps` when it's re-exported by that crate. The first
+    /// segment of a relative path, e.g. `deps`, is imported from the including scope by
+    /// [module()][Config::module].
+    ///
+    /// ```
+    /// use constuneval::{Aligned, Config, Style};
+    ///
+    /// let data: Aligned<u16, _> = Aligned::new([1_u8, 2]);
+    /// let config = Config::new().style(Style::Compact);
+    /// assert_eq!(
+    ///     config.to_string("DATA", &data, None),
+    ///     "const DATA: &'static ::constuneval::Aligned<u16, [u8]> = \
+    ///      &::constuneval::Aligned::new([1, 2]);"
+    /// );
+    /// assert_eq!(
+    ///     config.crate_path("crate::deps").to_string("DATA", &data, None),
+    ///     "const DATA: &'static crate::deps::Aligned<u16, [u8]> = \
+    ///      &crate::deps::Aligned::new([1, 2]);"
+    /// );
+    /// ```
+    pub fn crate_path(mut self, path: &str) -> Self {
+        self.crate_path = Some(path.to_string());
+        self
+    }
+
+    /// Set the path of [UnevalCow][crate::UnevalCow] in the emitted code,
+    /// `::constuneval::UnevalCow` by default, or in the path set by
+    /// [crate_path()][Config::crate_path].
+    ///
+    /// Use it when `UnevalCow` is re-exported under another name, e.g. `crate::deps::Cow`,
+    /// or imported where the code is included, `UnevalCow`. Relative paths are imported
+    /// as with [crate_path()][Config::crate_path].
+    ///
+    /// ```
+    /// use constuneval::{Config, Style, UnevalCow};
+    ///
+    /// let cow: UnevalCow<str> = UnevalCow::Owned("moo".to_string());
+    /// let config = Config::new().style(Style::Compact);
+    /// assert_eq!(
+    ///     config.to_string("COW", &cow, None),
+    ///     "const COW: ::constuneval::UnevalCow<'static, str> = \
+    ///      ::constuneval::UnevalCow::Borrowed(\"moo\");"
+    /// );
+    /// assert_eq!(
+    ///     config.cow_path("crate::deps::Cow").to_string("COW", &cow, None),
+    ///     "const COW: crate::deps::Cow<'static, str> = crate::deps::Cow::Borrowed(\"moo\");"
+    /// );
+    /// ```
+    pub fn cow_path(mut self, path: &str) -> Self {
+        self.cow_path = Some(path.to_string());
+        self
+    }
+
+    /// Emit floats from their bit pattern, as `f32::from_bits(0x3fc00000)`, disabled by
+    /// default.
+    ///
+    /// Float literals already round-trip exactly, but tables whose bit patterns matter,
+    /// e.g. NaN payloads, are easier to review this way. NaNs other than `f32::NAN` and
+    /// `f64::NAN` are always emitted from their bits. `from_bits` is only `const` since
+    /// Rust 1.83, the minimum version of this crate.
+    pub fn float_bits(mut self, enabled: bool) -> Self {
+        self.float_bits = enabled;
+        self
+    }
+
+    /// Suffix numeric literals with their type, as `1u8` and `3.5f32`, disabled by default.
+    ///
+    /// Without suffixes, the type of literals comes from the item type, which is wrong
+    /// when it is inferred loosely, e.g. in a tuple given to a generic function.
+    pub fn suffixes(mut self, enabled: bool) -> Self {
+        self.suffixes = enabled;
+        self
+    }
+
+    /// Set the radix of integer literals, decimal by default.
+    ///
+    /// ```
+    /// use constuneval::{Config, Radix};
+    ///
+    /// let config = Config::new().radix(Radix::Hex).suffixes(true);
+    /// assert_eq!(
+    ///     config.to_string("SBOX", &[0x63_u8, 0x7c], None),
+    ///     "const SBOX: [u8; 2] = [\n    0x63u8,\n    0x7cu8,\n];"
+    /// );
+    /// ```
+    pub fn radix(mut self, radix: Radix) -> Self {
+        self.radix = radix;
+        self
+    }
+
+    /// Separate the digits of integer literals with `_` in groups of `size`, as
+    /// `1_000_000` or `0xdead_beef`. `0`, the default, disables grouping.
+    pub fn digit_group(mut self, size: usize) -> Self {
+        self.digit_group = size;
+        self
+    }
+
+    /// Set the layout of the generated expressions, [Style::Pretty] by default.
+    ///
+    /// Pretty output of big tables has a line per element, which is slow for rustc and
+    /// makes diffs unreadable, [Style::Packed] or [Style::Wrapped] fix that.
+    ///
+    /// ```
+    /// use constuneval::{Config, Style};
+    ///
+    /// let config = Config::new().style(Style::Packed(4));
+    /// assert_eq!(
+    ///     config.to_string("TABLE", &[[0_u16; 5]; 2], None),
+    ///     "const TABLE: [[u16; 5]; 2] = [
+    ///     [0, 0, 0, 0, 0], [0, 0, 0, 0, 0],
+    /// ];"
+    /// );
+    /// ```
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Emit byte slices of at least `len` bytes as byte string literals, `b"..."`, which
+    /// are much smaller than arrays and faster to compile. The default is 16 bytes, shorter
+    /// slices being easier to read as arrays, and `usize::MAX` disables byte strings.
+    ///
+    /// Byte strings ignore [radix()][Config::radix] and [suffixes()][Config::suffixes],
+    /// which apply to the bytes of shorter slices.
+    ///
+    /// ```
+    /// use constuneval::Config;
+    ///
+    /// let config = Config::new().byte_strings(4);
+    /// assert_eq!(
+    ///     config.to_string("DATA", &(&b"\0abc\"\xff"[..], &b"ab"[..]), None),
+    ///     "const DATA: (&'static [u8], &'static [u8]) = (
+    ///     b\"\\0abc\\\"\\xff\",
+    ///     &[
+    ///         97,
+    ///         98,
+    ///     ],
+    /// );"
+    /// );
+    /// ```
+    pub fn byte_strings(mut self, len: usize) -> Self {
+        self.byte_strings = Some(len);
+        self
+    }
+
+    /// Write byte slices of at least `len` bytes to a sidecar `.bin` file next to the
+    /// generated one, emitted as `include_bytes!("file.bin")`.
+    ///
+    /// Smaller slices, and all of them when the code isn't written to a file, are emitted
+    /// as arrays or byte strings, see [byte_strings()][Config::byte_strings]. This keeps
+    /// huge tables from slowing down rustc.
+    ///
+    /// The files are named after the generated file and the hash of their content, e.g.
+    /// `tables.5cd5e1d9a2d8a3b4.bin`, identical slices sharing a file. They are listed in
+    /// `tables.rs.sidecars`, and the ones of a previous run which are not included anymore
+    /// are removed once the generated file is written. If writing it fails, the files this
+    /// run created are removed instead. No other file is removed.
+    pub fn sidecar_threshold(mut self, len: usize) -> Self {
+        self.sidecar_threshold = Some(len);
+        self
+    }
+
+    /// Obtain string with generated Rust code.
+    ///
+    /// # Panics
+    /// If generating the code fails, see [try_to_string()][Config::try_to_string].
+    pub fn to_string<T: Uneval + ?Sized>(&self, name: &str, value: &T, ty: Option<&str>) -> String {
+        self.try_to_string(name, value, ty)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Obtain string with generated Rust code.
+    ///
+    /// Fails if `name` isn't a valid identifier, if `value` can't be emitted or with the
+    /// `validate` feature, if the generated code isn't valid.
+    pub fn try_to_string<T: Uneval + ?Sized>(
+        &self,
+        name: &str,
+        value: &T,
+        ty: Option<&str>,
+    ) -> Result<String> {
+        let ty = item_type::<T>(ty)?;
+        self.render(name, &ty, None, emit_value(value, &ty))
+    }
+
+    /// Generate the Rust code into `out`, see [to_writer()][crate::to_writer].
+    pub fn to_writer<T: Uneval + ?Sized>(
+        &self,
+        out: impl io::Write,
+        name: &str,
+        value: &T,
+        ty: Option<&str>,
+    ) -> Result<()> {
+        let ty = item_type::<T>(ty)?;
+        let mut out = io::BufWriter::new(out);
+        self.write_io(&mut out, name, &ty, None, emit_value(value, &ty))?;
+        out.into_inner().map_err(|err| err.into_error())?;
+        Ok(())
+    }
+
+    /// Generate the Rust code and write it to `target`, see [to_file()][crate::to_file].
+    ///
+    /// Nothing is written if generating the code fails, see
+    /// [try_to_string()][Config::try_to_string].
+    pub fn to_file<T: Uneval + ?Sized>(
+        &self,
+        target: impl AsRef<Path>,
+        name: &str,
+        value: &T,
+        ty: Option<&str>,
+    ) -> Result<()> {
+        let target = target.as_ref();
+        let ty = item_type::<T>(ty)?;
+        let sidecars = Sidecars::new(target, self.write_mode);
+        self.write_target(target, sidecars, name, &ty, emit_value(value, &ty))
+    }
+
+    /// Generate a `&'static [T]` item from the elements of `iter`, see
+    /// [to_file_iter()][crate::to_file_iter].
+    pub fn to_file_iter<I>(
+        &self,
+        target: impl AsRef<Path>,
+        name: &str,
+        iter: I,
+        elem_ty: Option<&str>,
+    ) -> Result<()>
+    where
+        I: IntoIterator,
+        I::Item: Uneval,
+    {
+        let target = target.as_ref();
+        let elem_ty = item_type::<I::Item>(elem_ty)?;
+        let ty = format!("&'static [{}]", elem_ty);
+        let sidecars = Sidecars::new(target, self.write_mode);
+        self.write_target(target, sidecars, name, &ty, |e| {
+            e.import_item_type::<I::Item>(&elem_ty);
+            e.write_str("&")?;
+            emit_iter(e, iter).map(|_| ())
+        })
+    }
+
+    /// Generate a `[T; N]` item from the elements of `iter`, see
+    /// [to_file_array()][crate::to_file_array].
+    pub fn to_file_array<I>(
+        &self,
+        target: impl AsRef<Path>,
+        name: &str,
+        iter: I,
+        elem_ty: Option<&str>,
+    ) -> Result<()>
+    where
+        I: IntoIterator,
+        I::Item: Uneval,
+    {
+        let target = target.as_ref();
+        self.check_name(name)?;
+        let elem_ty = item_type::<I::Item>(elem_ty)?;
+        let mut sidecars = Sidecars::new(target, self.write_mode);
+        // `N` is only known once the elements are emitted, so they are streamed to a
+        // scratch file, which is copied to the item once its type is written
+        let scratch = ScratchFile::new(target);
+        let (len, imports) = scratch.write(|out| {
+            let mut e = Emitter::new(out, name, self, Some(&mut sidecars));
+            e.import_item_type::<I::Item>(&elem_ty);
+            let res = emit_iter(&mut e, iter);
+            let imports = e.finish();
+            Ok((res?, imports))
+        })?;
+        let ty = format!("[{}; {}]", elem_ty, len);
+        self.write_target(target, sidecars, name, &ty, |e| {
+            for path in &imports {
+                e.import(path);
+            }
+            scratch.copy_to(e)
+        })
+    }
+
+    /// Generate the Rust code into `file_name` in `OUT_DIR`, see
+    /// [to_out_dir()][crate::to_out_dir].
+    pub fn to_out_dir<T: Uneval + ?Sized>(
+        &self,
+        file_name: &str,
+        name: &str,
+        value: &T,
+        ty: Option<&str>,
+    ) -> Result<()> {
+        self.to_file(crate::out_path(file_name)?, name, value, ty)
+    }
+
+    /// Name of an option set for the file rather than the item, which a
+    /// [ConstWriter][crate::ConstWriter] can't apply to its items.
+    pub(crate) fn file_option(&self) -> Option<&'static str> {
+        if self.module.is_some() {
+            Some("module")
+        } else if self.header != Header::default() {
+            Some("header")
+        } else if self.write_mode != WriteMode::default() {
+            Some("write_mode")
+        } else {
+            None
+        }
+    }
+
+    /// Rewrite the paths of constuneval items in `ty`, written `::constuneval::Name` by
+    /// [Uneval::const_type], as set by [crate_path()][Config::crate_path] and
+    /// [cow_path()][Config::cow_path], adding the paths to import to `imports`.
+    fn resolve_type(&self, ty: &str, imports: &mut BTreeSet<String>) -> String {
+        rewrite_crate_paths(ty, |name| {
+            let path = self.item_path(name);
+            imports.extend(path_import(&path));
+            path
+        })
+    }
+
+    /// Path of the constuneval item `name` in the emitted code.
+    pub(crate) fn item_path(&self, name: &str) -> String {
+        match (&self.cow_path, name) {
+            (Some(path), "UnevalCow") => path.clone(),
+            _ => format!(
+                "{}::{}",
+                self.crate_path.as_deref().unwrap_or(DEFAULT_CRATE_PATH),
+                name
+            ),
+        }
+    }
+
+    fn render(
+        &self,
+        name: &str,
+        ty: &str,
+        sidecars: Option<&mut Sidecars>,
+        emit: impl FnOnce(&mut Emitter<'_>) -> Result<()>,
+    ) -> Result<String> {
+        let mut out = String::new();
+        self.write_emitted(&mut out, name, ty, sidecars, emit)?;
+        #[cfg(feature = "validate")]
+        crate::validate(&out)?;
+        Ok(out)
+    }
+
+    fn write_target(
+        &self,
+        target: &Path,
+        mut sidecars: Sidecars,
+        name: &str,
+        ty: &str,
+        emit: impl FnOnce(&mut Emitter<'_>) -> Result<()>,
+    ) -> Result<()> {
+        write_file_with(target, self.write_mode, |out| {
+            self.header.write(out, |out| {
+                self.write_io(out, name, ty, Some(&mut sidecars), emit)
+            })
+        })?;
+        Ok(sidecars.finish()?)
+    }
+
+    /// Stream the item to `out`, or write it at once after validating it with the
+    /// `validate` feature, ending with a newline as the items of a
+    /// [ConstWriter][crate::ConstWriter].
+    fn write_io(
+        &self,
+        out: &mut dyn io::Write,
+        name: &str,
+        ty: &str,
+        sidecars: Option<&mut Sidecars>,
+        emit: impl FnOnce(&mut Emitter<'_>) -> Result<()>,
+    ) -> Result<()> {
+        if cfg!(feature = "validate") {
+            let code = self.render(name, ty, sidecars, emit)?;
+            out.write_all(code.as_bytes())?;
+            return Ok(out.write_all(b"\n")?);
+        }
+        let mut out = IoWriter::new(out);
+        let res = self
+            .write_emitted(&mut out, name, ty, sidecars, emit)
+            .and_then(|_| Ok(out.write_char('\n')?));
+        out.finish(res)
+    }
+
+    /// Write an item of a [ConstWriter][crate::ConstWriter], returns the paths to import
+    /// when it's `in_module`.
+    pub(crate) fn write_item<T: Uneval + ?Sized>(
+        &self,
+        out: &mut dyn fmt::Write,
+        name: &str,
+        value: &T,
+        ty: Option<&str>,
+        sidecars: Option<&mut Sidecars>,
+        in_module: bool,
+    ) -> Result<BTreeSet<String>> {
+        let ty = item_type::<T>(ty)?;
+        self.write_item_emitted(out, name, &ty, sidecars, in_module, emit_value(value, &ty))
+    }
+
+    fn write_emitted(
+        &self,
+        out: &mut dyn fmt::Write,
+        name: &str,
+        ty: &str,
+        sidecars: Option<&mut Sidecars>,
+        emit: impl FnOnce(&mut Emitter<'_>) -> Result<()>,
+    ) -> Result<()> {
+        match &self.module {
+            Some(module) => write_module(out, module, |out| {
+                let imports = self.write_item_emitted(out, name, ty, sidecars, true, emit)?;
+                out.write_char('\n')?;
+                Ok(imports)
+            }),
+            None => self
+                .write_item_emitted(out, name, ty, sidecars, false, emit)
+                .map(|_| ()),
+        }
+    }
+
+    fn write_item_emitted(
+        &self,
+        out: &mut dyn fmt::Write,
+        name: &str,
+        ty: &str,
+        sidecars: Option<&mut Sidecars>,
+        in_module: bool,
+        emit: impl FnOnce(&mut Emitter<'_>) -> Result<()>,
+    ) -> Result<BTreeSet<String>> {
+        self.check_name(name)?;
+        for doc in &self.docs {
+            match doc.as_str() {
+                "" => writeln!(out, "///")?,
+                doc => writeln!(out, "/// {}", doc)?,
+            }
+        }
+        for attr in &self.attrs {
+            writeln!(out, "#[{}]", attr)?;
+        }
+        let vis = if in_module {
+            self.vis.in_module()
+        } else {
+            self.vis.to_string()
+        };
+        let mut imports = BTreeSet::new();
+        let ty = self.resolve_type(ty, &mut imports);
+        write!(out, "{}{} {}: {} = ", vis, self.kind, name, ty)?;
+        let mut emitter = Emitter::new(out, name, self, sidecars);
+        for path in &imports {
+            emitter.import(path);
+        }
+        let res = emit(&mut emitter);
+        // the warnings may tell why it failed
+        let imports = emitter.finish();
+        res?;
+        out.write_str(";")?;
+        Ok(imports)
+    }
+
+    fn check_name(&self, name: &str) -> Result<()> {
+        // `const _` is allowed, `static _` isn't
+        let underscore = name == "_" && self.kind != ItemKind::Const;
+        if is_ident(name) && !underscore {
+            Ok(())
+        } else {
+            Err(Error::InvalidName(name.to_string()))
+        }
+    }
+}
+
+/// Whether `name` is an identifier or `_`.
+///
+/// Keywords reserved by any edition are rejected, as the edition of the crate including
+/// the code isn't known.
+fn is_ident(name: &str) -> bool {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+        "true", "type", "unsafe", "use", "where", "while",
+    ];
+    const RESERVED: &[&str] = &[
+        "abstract", "become", "box", "do", "final", "gen", "macro", "override", "priv", "try",
+        "typeof", "unsized", "virtual", "yield",
+    ];
+    // path segments which can't be raw identifiers either
+    const NOT_RAW: &[&str] = &["_", "crate", "self", "Self", "super"];
+    let ident = name.strip_prefix("r#").unwrap_or(name);
+    let mut chars = ident.chars();
+    let valid = match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' => chars.all(|c| c.is_alphanumeric() || c == '_'),
+        _ => false,
+    };
+    if ident != name {
+        valid && !NOT_RAW.contains(&ident)
+    } else {
+        valid && !KEYWORDS.contains(&name) && !RESERVED.contains(&name)
+    }
+}
+
+/// Write `pub mod name { ... }` around the items written by `write`, followed by `use`
+/// statements for the paths it returns.
+pub(crate) fn write_module(
+    out: &mut dyn fmt::Write,
+    name: &str,
+    write: impl FnOnce(&mut dyn fmt::Write) -> Result<BTreeSet<String>>,
+) -> Result<()> {
+    if !is_ident(name) || name == "_" {
+        return Err(Error::InvalidName(name.to_string()));
+    }
+    writeln!(out, "pub mod {} {{", name)?;
+    let imports = write(out)?;
+    let mut names = BTreeMap::new();
+    for path in &imports {
+        if let Some(other) = names.insert(import_name(path), path) {
+            return Err(Error::Unsupported {
+                path: name.to_string(),
+                message: format!("`{}` and `{}` can't both be imported", other, path),
+            });
+        }
+    }
+    for path in imports {
+        writeln!(out, "use {};", path)?;
+    }
+    Ok(out.write_str("}")?)
+}
+
+/// Path to import from the scope including the code for `path` to resolve, i.e. its first
+/// segment when it's relative, `super::deps` for `deps::Cow`.
+pub(crate) fn path_import(path: &str) -> Option<String> {
+    if path.starts_with("::") || path.starts_with("crate::") {
+        return None;
+    }
+    let relative = path.strip_prefix("self::").unwrap_or(path);
+    let first = relative.split("::").next().unwrap_or(relative);
+    Some(format!("super::{}", first))
+}
+
+/// Replace the paths of constuneval items in `code`, `::constuneval::Name`, by
+/// `rewrite(name)`.
+///
+/// Only paths starting with `::constuneval` are, not `other::constuneval::Name`, and
+/// string and char literals are left untouched.
+pub(crate) fn rewrite_crate_paths(code: &str, mut rewrite: impl FnMut(&str) -> String) -> String {
+    let prefix = format!("{}::", DEFAULT_CRATE_PATH);
+    let bytes = code.as_bytes();
+    let mut out = String::with_capacity(code.len());
+    // `code[copied..i]` is still to be copied to `out`
+    let (mut copied, mut i) = (0, 0);
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => i = literal_end(bytes, i + 1, b'"'),
+            b'\'' if bytes.get(i + 1) == Some(&b'\\') => i = literal_end(bytes, i + 1, b'\''),
+            b'\'' => {
+                // a char literal, or a lifetime
+                let len = code[i + 1..].chars().next().map_or(0, char::len_utf8);
+                match bytes.get(i + 1 + len) {
+                    Some(b'\'') => i += len + 2,
+                    _ => i += 1,
+                }
+            }
+            b':' if code[i..].starts_with(&prefix)
+                && !(i > 0 && (bytes[i - 1] == b'_' || bytes[i - 1].is_ascii_alphanumeric())) =>
+            {
+                let start = i + prefix.len();
+                let len = code[start..]
+                    .find(|c: char| !c.is_alphanumeric() && c != '_')
+                    .unwrap_or(code.len() - start);
+                out.push_str(&code[copied..i]);
+                out.push_str(&rewrite(&code[start..start + len]));
+                i = start + len;
+                copied = i;
+            }
+            _ => i += 1,
+        }
+    }
+    out.push_str(&code[copied..]);
+    out
+}
+
+/// Index following the literal whose content starts at `start`, ending with `quote`.
+fn literal_end(bytes: &[u8], start: usize, quote: u8) -> usize {
+    let mut i = start;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b if b == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Name given by the `use` statement of `path`, its last segment.
+pub(crate) fn import_name(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+/// Type of an item, `ty` or the one inferred for `T`.
+fn item_type<T: Uneval + ?Sized>(ty: Option<&str>) -> Result<String> {
+    match ty {
+        Some(ty) => Ok(ty.to_string()),
+        None => T::const_type(),
+    }
+}
+
+/// Emit `value` in an item of type `ty`, registering the types of `T` it names.
+fn emit_value<'v, T: Uneval + ?Sized>(
+    value: &'v T,
+    ty: &'v str,
+) -> impl FnOnce(&mut Emitter<'_>) -> Result<()> + 'v {
+    move |e| {
+        e.import_item_type::<T>(ty);
+        value.uneval(e)
+    }
+}
+
+/// Emit an array expression with the elements of `iter`, returns their number.
+fn emit_iter<I>(e: &mut Emitter<'_>, iter: I) -> Result<usize>
+where
+    I: IntoIterator,
+    I::Item: Uneval,
+{
+    let mut array = e.array_expr();
+    let mut count = 0;
+    for value in iter {
+        array.entry(&value);
+        count += 1;
+    }
+    array.finish()?;
+    Ok(count)
+}
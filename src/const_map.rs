@@ -0,0 +1,183 @@
+//! Map over a sorted slice, which can be constructed in const context.
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+
+use crate::uneval::{uneval_map, UnevalFn};
+use crate::{Emitter, Result, Uneval};
+
+/// Map stored as a slice of entries sorted by key, looked up with binary search.
+///
+/// It is created with a `const fn`, so it can be a `const` or `static` item, and it is
+/// emitted as `::constuneval::ConstMap::new(&[...])`, see
+/// [Config::crate_path][crate::Config::crate_path].
+///
+/// ```
+/// use constuneval::{to_string, ConstMap};
+///
+/// let entries = vec![(1_u8, "one"), (3, "three")];
+/// let map = ConstMap::new(&entries);
+/// assert_eq!(map.get(&3), Some(&"three"));
+/// assert_eq!(
+///     to_string("MAP", &map, None),
+///     "const MAP: ::constuneval::ConstMap<'static, u8, &'static str> = \
+///      ::constuneval::ConstMap::new(
+///     &[
+///         (
+///             1,
+///             \"one\",
+///         ),
+///         (
+///             3,
+///             \"three\",
+///         ),
+///     ],
+/// );"
+/// );
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ConstMap<'a, K, V> {
+    entries: &'a [(K, V)],
+}
+
+impl<'a, K, V> ConstMap<'a, K, V> {
+    /// Create a map from `entries`, which must be sorted by key without duplicates.
+    ///
+    /// This isn't checked here but when the map is emitted, lookups give wrong results if
+    /// the keys are not sorted.
+    pub const fn new(entries: &'a [(K, V)]) -> Self {
+        ConstMap { entries }
+    }
+
+    /// Number of entries in the map.
+    pub const fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the map is empty.
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries of the map, sorted by key.
+    pub const fn as_slice(&self) -> &'a [(K, V)] {
+        self.entries
+    }
+
+    /// Value corresponding to `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&'a V>
+    where
+        Q: Ord + ?Sized,
+        K: Borrow<Q>,
+    {
+        self.get_key_value(key).map(|(_, value)| value)
+    }
+
+    /// Entry corresponding to `key`.
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&'a K, &'a V)>
+    where
+        Q: Ord + ?Sized,
+        K: Borrow<Q>,
+    {
+        let entries = self.entries;
+        entries
+            .binary_search_by(|(k, _)| k.borrow().cmp(key))
+            .ok()
+            .map(|i| (&entries[i].0, &entries[i].1))
+    }
+
+    /// Whether the map contains `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        Q: Ord + ?Sized,
+        K: Borrow<Q>,
+    {
+        self.get_key_value(key).is_some()
+    }
+
+    /// Entries whose key is in `range`, sorted by key.
+    pub fn range<Q, R>(&self, range: R) -> std::slice::Iter<'a, (K, V)>
+    where
+        Q: Ord + ?Sized,
+        K: Borrow<Q>,
+        R: RangeBounds<Q>,
+    {
+        let entries = self.entries;
+        let start = match range.start_bound() {
+            Bound::Included(start) => entries.partition_point(|(k, _)| k.borrow() < start),
+            Bound::Excluded(start) => entries.partition_point(|(k, _)| k.borrow() <= start),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => entries.partition_point(|(k, _)| k.borrow() <= end),
+            Bound::Excluded(end) => entries.partition_point(|(k, _)| k.borrow() < end),
+            Bound::Unbounded => entries.len(),
+        };
+        entries[start..end.max(start)].iter()
+    }
+
+    /// Entries of the map, sorted by key.
+    pub fn iter(&self) -> std::slice::Iter<'a, (K, V)> {
+        self.entries.iter()
+    }
+
+    /// Keys of the map, sorted.
+    pub fn keys(&self) -> impl Iterator<Item = &'a K> + 'a {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    /// Values of the map, sorted by key.
+    pub fn values(&self) -> impl Iterator<Item = &'a V> + 'a {
+        self.entries.iter().map(|(_, value)| value)
+    }
+}
+
+impl<'a, K, V> IntoIterator for ConstMap<'a, K, V> {
+    type Item = &'a (K, V);
+    type IntoIter = std::slice::Iter<'a, (K, V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for ConstMap<'_, K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.entries.iter().map(|(k, v)| (k, v)))
+            .finish()
+    }
+}
+
+impl<K: Uneval + Ord, V: Uneval> Uneval for ConstMap<'_, K, V> {
+    /// Fails if the keys are not sorted or have duplicates.
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        for (i, pair) in self.entries.windows(2).enumerate() {
+            match pair[0].0.cmp(&pair[1].0) {
+                Ordering::Less => {}
+                Ordering::Equal => {
+                    return Err(e.error(format_args!("keys {} and {} are equal", i, i + 1)))
+                }
+                Ordering::Greater => {
+                    return Err(e.error(format_args!("keys {} and {} are not sorted", i, i + 1)))
+                }
+            }
+        }
+        let path = e.crate_path("ConstMap");
+        e.tuple_expr(&format!("{}::new", path))
+            .field(&UnevalFn(|e: &mut Emitter<'_>| {
+                uneval_map(self.entries.iter().map(|(k, v)| (k, v)), e)
+            }))
+            .finish()
+    }
+
+    fn const_type() -> Result<String> {
+        Ok(format!(
+            "::constuneval::ConstMap<'static, {}, {}>",
+            K::const_type()?,
+            V::const_type()?
+        ))
+    }
+}
@@ -0,0 +1,483 @@
+//! Writer used by [Uneval] implementations to produce Rust expressions.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{self, Write};
+use std::mem;
+
+use crate::config::{import_name, path_import, DEFAULT_BYTE_STRINGS};
+use crate::file::Sidecars;
+use crate::{Config, Error, Result, Style, Uneval};
+
+enum Segment {
+    Field(String),
+    Index(usize),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Layout {
+    Struct,
+    /// `plain` for tuple expressions without a path, which need a trailing comma with a
+    /// single field.
+    Tuple {
+        plain: bool,
+    },
+    Array,
+}
+
+/// Sink for the Rust code generated by [Uneval] implementations.
+///
+/// `Emitter` takes care of the layout (indentation, separators, trailing commas) so
+/// implementations only have to describe *what* to construct, in the same way
+/// [std::fmt::Formatter] does for `Debug` with `debug_struct()`, `debug_tuple()` and
+/// `debug_list()`. It also keeps track of the path of the value being emitted, which is
+/// used by [error()][Emitter::error] to report where a problem occurred.
+///
+/// Raw tokens can be written through the [std::fmt::Write] implementation, e.g. with
+/// `write!(emitter, "{}", 42)`.
+pub struct Emitter<'a> {
+    out: &'a mut dyn fmt::Write,
+    indent: usize,
+    on_newline: bool,
+    column: usize,
+    compact: bool,
+    /// Output of an array element which is held until it's known to fit on the line.
+    buffer: Option<String>,
+    root: &'a str,
+    path: Vec<Segment>,
+    /// Path of the first value reported, message and number of reports.
+    warnings: Vec<(String, String, usize)>,
+    /// Index in `warnings` of each message.
+    warning_indices: HashMap<String, usize>,
+    imports: BTreeSet<String>,
+    config: &'a Config,
+    sidecars: Option<&'a mut Sidecars>,
+}
+
+impl<'a> Emitter<'a> {
+    pub(crate) fn new(
+        out: &'a mut dyn fmt::Write,
+        root: &'a str,
+        config: &'a Config,
+        sidecars: Option<&'a mut Sidecars>,
+    ) -> Self {
+        Emitter {
+            out,
+            indent: 0,
+            on_newline: false,
+            column: 0,
+            compact: config.style == Style::Compact,
+            buffer: None,
+            root,
+            path: Vec::new(),
+            warnings: Vec::new(),
+            warning_indices: HashMap::new(),
+            imports: BTreeSet::new(),
+            config,
+            sidecars,
+        }
+    }
+
+    pub(crate) fn config(&self) -> &Config {
+        self.config
+    }
+
+    /// Whether the value is written on a single line.
+    pub(crate) fn is_compact(&self) -> bool {
+        self.compact
+    }
+
+    /// Emit an expression constructing `value`.
+    pub fn emit<T: Uneval + ?Sized>(&mut self, value: &T) -> Result<()> {
+        value.uneval(self)
+    }
+
+    /// Emit an expression constructing a `&'static` reference to `value`.
+    pub fn emit_ref<T: Uneval + ?Sized>(&mut self, value: &T) -> Result<()> {
+        value.uneval_ref(self)
+    }
+
+    /// Emit a `&'static [u8; N]` expression containing `bytes`.
+    ///
+    /// It's `include_bytes!("file.bin")` with [Config::sidecar_threshold], a byte string
+    /// literal, `b"..."`, from the length set with [Config::byte_strings], and an array of
+    /// integer literals, `&[...]`, for shorter slices.
+    pub fn emit_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        self.write_bytes(bytes, false)
+    }
+
+    /// Emit a `[u8; N]` expression containing `bytes`, as with
+    /// [emit_bytes()][Emitter::emit_bytes].
+    pub(crate) fn emit_byte_array(&mut self, bytes: &[u8]) -> Result<()> {
+        self.write_bytes(bytes, true)
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8], array: bool) -> Result<()> {
+        let deref = if array { "*" } else { "" };
+        let threshold = self.config.sidecar_threshold;
+        if let Some(sidecars) = &mut self.sidecars {
+            if threshold.is_some_and(|len| bytes.len() >= len) {
+                let name = sidecars.add(bytes)?;
+                return Ok(write!(self, "{}include_bytes!({:?})", deref, name)?);
+            }
+        }
+        let min = self.config.byte_strings.unwrap_or(DEFAULT_BYTE_STRINGS);
+        if bytes.len() < min {
+            if !array {
+                self.write_str("&")?;
+            }
+            return self.array_expr().entries(bytes).finish();
+        }
+        let mut literal = String::with_capacity(bytes.len() + 4);
+        literal.push_str(deref);
+        literal.push_str("b\"");
+        for &byte in bytes {
+            match byte {
+                b'"' => literal.push_str("\\\""),
+                b'\\' => literal.push_str("\\\\"),
+                b'\n' => literal.push_str("\\n"),
+                b'\r' => literal.push_str("\\r"),
+                b'\t' => literal.push_str("\\t"),
+                b'\0' => literal.push_str("\\0"),
+                b' '..=b'~' => literal.push(byte as char),
+                _ => write!(literal, "\\x{:02x}", byte)?,
+            }
+        }
+        literal.push('"');
+        Ok(self.write_str(&literal)?)
+    }
+
+    /// Path of the value being emitted, e.g. `TABLE.some_table[3].field`.
+    pub fn path(&self) -> String {
+        let mut path = self.root.to_string();
+        for segment in &self.path {
+            match segment {
+                Segment::Field(name) => write!(path, ".{}", name),
+                Segment::Index(i) => write!(path, "[{}]", i),
+            }
+            .expect("a Display implementation returned an error unexpectedly");
+        }
+        path
+    }
+
+    /// Create an [Error::Unsupported] for the value being emitted.
+    ///
+    /// ```
+    /// use constuneval::{Emitter, Result, Uneval};
+    ///
+    /// struct Handle(std::fs::File);
+    ///
+    /// impl Uneval for Handle {
+    ///     fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+    ///         Err(e.error("files can't be created in const context"))
+    ///     }
+    /// }
+    /// ```
+    pub fn error(&self, message: impl fmt::Display) -> Error {
+        Error::Unsupported {
+            path: self.path(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Report a problem which doesn't prevent generating the code.
+    ///
+    /// Warnings are displayed with [warning()][crate::warning] once the item is generated,
+    /// or failed to, from a build script, and ignored otherwise. Repeated messages are
+    /// reported once, with the path of the first value and the number of repetitions.
+    pub fn warn(&mut self, message: impl fmt::Display) {
+        let message = message.to_string();
+        match self.warning_indices.get(&message) {
+            Some(&index) => self.warnings[index].2 += 1,
+            None => {
+                self.warning_indices
+                    .insert(message.clone(), self.warnings.len());
+                self.warnings.push((self.path(), message, 1));
+            }
+        }
+    }
+
+    /// Register a type the generated code refers to by `path`, e.g.
+    /// `::constuneval::ConstMap`, which is imported with a `use` statement by
+    /// [Config::module].
+    pub fn import(&mut self, path: &str) {
+        self.imports.insert(path.to_string());
+    }
+
+    /// Register what the generated code needs for `path` to resolve in a [Config::module]:
+    /// when it's relative, i.e. it doesn't start with `::` or `crate::`, its first segment
+    /// is registered with [import()][Emitter::import] from the scope including the code,
+    /// e.g. `super::tables` for `tables::Entry`.
+    pub fn import_relative(&mut self, path: &str) {
+        if let Some(import) = path_import(path) {
+            self.import(&import);
+        }
+    }
+
+    /// Path of the constuneval item `name` in the emitted code, see [Config::crate_path],
+    /// registered with [import_relative()][Emitter::import_relative].
+    pub(crate) fn crate_path(&mut self, name: &str) -> String {
+        let path = self.config.item_path(name);
+        self.import_relative(&path);
+        path
+    }
+
+    /// Register `T` and the types of its generic arguments as with
+    /// [import()][Emitter::import], from the paths given by [std::any::type_name].
+    ///
+    /// Nothing is imported for primitive and standard library types. Types of the build
+    /// script are imported from the root of the crate including the code, `crate::`, and
+    /// other ones from their crate.
+    pub fn import_type<T: ?Sized>(&mut self) {
+        for path in crate::ty::import_paths::<T>() {
+            self.import(&path);
+        }
+    }
+
+    /// Register the types of `T`, as with [import_type()][Emitter::import_type], which
+    /// the item type `ty` refers to by name.
+    pub(crate) fn import_item_type<T: ?Sized>(&mut self, ty: &str) {
+        let named = crate::ty::named_types(ty);
+        for path in crate::ty::import_paths::<T>() {
+            if named.contains(&import_name(&path)) {
+                self.import(&path);
+            }
+        }
+    }
+
+    /// Report the warnings and return the paths to import.
+    pub(crate) fn finish(self) -> BTreeSet<String> {
+        if crate::is_build_script() {
+            for (path, message, count) in &self.warnings {
+                match count {
+                    1 => crate::warning(&format!("{}: {}", path, message)),
+                    n => crate::warning(&format!("{}: {} (and {} more)", path, message, n - 1)),
+                }
+            }
+        }
+        self.imports
+    }
+
+    /// Start a struct expression, `Path { field: value, .. }`.
+    pub fn struct_expr<'b>(&'b mut self, path: &str) -> StructExpr<'b, 'a> {
+        let result = self.write_str(path).and_then(|_| self.write_str(" {"));
+        StructExpr {
+            emitter: self,
+            result: result.map_err(Error::from),
+            fields: 0,
+        }
+    }
+
+    /// Start a tuple-like expression, `Path(value, ..)`.
+    ///
+    /// An empty `path` produces a plain tuple expression, `(value, ..)`.
+    pub fn tuple_expr<'b>(&'b mut self, path: &str) -> TupleExpr<'b, 'a> {
+        let result = self.write_str(path).and_then(|_| self.write_str("("));
+        TupleExpr {
+            emitter: self,
+            result: result.map_err(Error::from),
+            fields: 0,
+            plain: path.is_empty(),
+        }
+    }
+
+    /// Start an array expression, `[value, ..]`.
+    pub fn array_expr<'b>(&'b mut self) -> ArrayExpr<'b, 'a> {
+        let result = self.write_str("[");
+        ArrayExpr {
+            emitter: self,
+            result: result.map_err(Error::from),
+            entries: 0,
+        }
+    }
+
+    fn entry(
+        &mut self,
+        layout: Layout,
+        index: usize,
+        segment: Segment,
+        f: impl FnOnce(&mut Self) -> Result<()>,
+    ) -> Result<()> {
+        self.path.push(segment);
+        if self.compact {
+            self.write_str(match (layout, index) {
+                (Layout::Struct, 0) => " ",
+                (_, 0) => "",
+                _ => ", ",
+            })?;
+            f(self)?;
+        } else {
+            self.indent += 1;
+            match (layout, self.config.style) {
+                (Layout::Array, Style::Packed(per_line)) => {
+                    let newline = index % per_line.max(1) == 0;
+                    self.write_str(if newline { "\n" } else { " " })?;
+                    self.compact = true;
+                    let res = f(self);
+                    self.compact = false;
+                    res?;
+                }
+                (Layout::Array, Style::Wrapped(width)) => {
+                    self.compact = true;
+                    self.buffer = Some(String::new());
+                    let res = f(self);
+                    let entry = self.buffer.take().unwrap_or_default();
+                    self.compact = false;
+                    res?;
+                    // the entry is followed by a comma
+                    let fits = self.column + 1 + entry.chars().count() < width;
+                    self.write_str(if index > 0 && fits { " " } else { "\n" })?;
+                    self.write_str(&entry)?;
+                }
+                _ => {
+                    self.write_str("\n")?;
+                    f(self)?;
+                }
+            }
+            self.write_str(",")?;
+            self.indent -= 1;
+        }
+        self.path.pop();
+        Ok(())
+    }
+
+    fn close(&mut self, layout: Layout, entries: usize, closing: &str) -> Result<()> {
+        if entries > 0 {
+            self.write_str(match layout {
+                _ if !self.compact => "\n",
+                Layout::Struct => " ",
+                Layout::Tuple { plain: true } if entries == 1 => ",",
+                _ => "",
+            })?;
+        }
+        Ok(self.write_str(closing)?)
+    }
+}
+
+impl fmt::Write for Emitter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if let Some(buffer) = &mut self.buffer {
+            buffer.push_str(s);
+            return Ok(());
+        }
+        for line in s.split_inclusive('\n') {
+            if self.on_newline && line != "\n" {
+                for _ in 0..self.indent {
+                    self.out.write_str("    ")?;
+                }
+                self.column = 4 * self.indent;
+            }
+            self.on_newline = line.ends_with('\n');
+            self.column = if self.on_newline {
+                0
+            } else {
+                self.column + line.chars().count()
+            };
+            self.out.write_str(line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Builder for struct expressions, created by [Emitter::struct_expr].
+pub struct StructExpr<'b, 'a> {
+    emitter: &'b mut Emitter<'a>,
+    result: Result<()>,
+    fields: usize,
+}
+
+impl StructExpr<'_, '_> {
+    /// Add a `name: value` field.
+    pub fn field<T: Uneval + ?Sized>(&mut self, name: &str, value: &T) -> &mut Self {
+        if self.result.is_ok() {
+            let segment = Segment::Field(name.to_string());
+            self.result = self
+                .emitter
+                .entry(Layout::Struct, self.fields, segment, |e| {
+                    e.write_str(name)?;
+                    e.write_str(": ")?;
+                    value.uneval(e)
+                });
+        }
+        self.fields += 1;
+        self
+    }
+
+    /// Finish the expression.
+    pub fn finish(&mut self) -> Result<()> {
+        mem::replace(&mut self.result, Ok(()))
+            .and_then(|_| self.emitter.close(Layout::Struct, self.fields, "}"))
+    }
+}
+
+/// Builder for tuple-like expressions, created by [Emitter::tuple_expr].
+pub struct TupleExpr<'b, 'a> {
+    emitter: &'b mut Emitter<'a>,
+    result: Result<()>,
+    fields: usize,
+    plain: bool,
+}
+
+impl TupleExpr<'_, '_> {
+    /// Add a positional field.
+    pub fn field<T: Uneval + ?Sized>(&mut self, value: &T) -> &mut Self {
+        if self.result.is_ok() {
+            let layout = Layout::Tuple { plain: self.plain };
+            let segment = Segment::Field(self.fields.to_string());
+            self.result = self
+                .emitter
+                .entry(layout, self.fields, segment, |e| value.uneval(e));
+        }
+        self.fields += 1;
+        self
+    }
+
+    /// Finish the expression.
+    pub fn finish(&mut self) -> Result<()> {
+        mem::replace(&mut self.result, Ok(())).and_then(|_| {
+            let layout = Layout::Tuple { plain: self.plain };
+            self.emitter.close(layout, self.fields, ")")
+        })
+    }
+}
+
+/// Builder for array expressions, created by [Emitter::array_expr].
+pub struct ArrayExpr<'b, 'a> {
+    emitter: &'b mut Emitter<'a>,
+    result: Result<()>,
+    entries: usize,
+}
+
+impl ArrayExpr<'_, '_> {
+    /// Add an element.
+    pub fn entry<T: Uneval + ?Sized>(&mut self, value: &T) -> &mut Self {
+        if self.result.is_ok() {
+            let segment = Segment::Index(self.entries);
+            self.result = self
+                .emitter
+                .entry(Layout::Array, self.entries, segment, |e| value.uneval(e));
+        }
+        self.entries += 1;
+        self
+    }
+
+    /// Add every element of `values`.
+    pub fn entries<'c, T, I>(&mut self, values: I) -> &mut Self
+    where
+        T: Uneval + 'c,
+        I: IntoIterator<Item = &'c T>,
+    {
+        for value in values {
+            if self.result.is_err() {
+                break;
+            }
+            self.entry(value);
+        }
+        self
+    }
+
+    /// Finish the expression.
+    pub fn finish(&mut self) -> Result<()> {
+        mem::replace(&mut self.result, Ok(()))
+            .and_then(|_| self.emitter.close(Layout::Array, self.entries, "]"))
+    }
+}
@@ -0,0 +1,113 @@
+//! Error type of the crate.
+
+use std::fmt;
+use std::io;
+
+/// Errors which can happen while generating code.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Reading or writing a file failed.
+    Io(io::Error),
+    /// A `fmt::Write` or `Debug` implementation returned an error.
+    Fmt(fmt::Error),
+    /// The name of an item is not a valid identifier.
+    InvalidName(String),
+    /// The path of a [Visibility::restricted()][crate::Visibility::restricted] isn't a
+    /// module path starting with `crate`, `self` or `super`.
+    InvalidVisibility(String),
+    /// The same name was used for more than one item.
+    DuplicateNames(Vec<String>),
+    /// The configuration of an item of a [ConstWriter][crate::ConstWriter] sets an option
+    /// of the file, which has to be set on the writer.
+    FileOption {
+        /// Name of the item.
+        item: String,
+        /// Name of the option, e.g. `module`.
+        option: &'static str,
+    },
+    /// A value can't be written as a const expression.
+    Unsupported {
+        /// Path of the value inside the generated item, e.g. `TABLE.some_table[3].field`.
+        path: String,
+        /// Description of the problem.
+        message: String,
+    },
+    /// The type of an item couldn't be inferred and has to be given explicitly.
+    TypeInference(String),
+    /// The function was not called from a build script, see
+    /// [is_build_script()][crate::is_build_script].
+    NotBuildScript,
+    /// The generated code isn't valid Rust.
+    #[cfg(feature = "validate")]
+    Validation(crate::ValidationError),
+}
+
+/// Result type of the crate.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "I/O error: {}", err),
+            Error::Fmt(_) => f.write_str("formatting error"),
+            Error::InvalidName(name) => write!(f, "`{}` is not a valid item name", name),
+            Error::InvalidVisibility(path) => write!(
+                f,
+                "`{}` is not a `pub(in ...)` path starting with `crate`, `self` or `super`",
+                path
+            ),
+            Error::DuplicateNames(names) => {
+                f.write_str("duplicate item names:")?;
+                for (i, name) in names.iter().enumerate() {
+                    write!(f, "{} `{}`", if i == 0 { "" } else { "," }, name)?;
+                }
+                Ok(())
+            }
+            Error::FileOption { item, option } => write!(
+                f,
+                "{}: `{}` applies to the whole file, set it on the ConstWriter",
+                item, option
+            ),
+            Error::Unsupported { path, message } => write!(f, "{}: {}", path, message),
+            Error::TypeInference(message) => write!(f, "can't infer item type: {}", message),
+            Error::NotBuildScript => f.write_str(
+                "constuneval must be called from a build script, \
+                 `OUT_DIR`, `TARGET`, `HOST` or `NUM_JOBS` is not set",
+            ),
+            #[cfg(feature = "validate")]
+            Error::Validation(err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Fmt(err) => Some(err),
+            #[cfg(feature = "validate")]
+            Error::Validation(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<fmt::Error> for Error {
+    fn from(err: fmt::Error) -> Self {
+        Error::Fmt(err)
+    }
+}
+
+#[cfg(feature = "validate")]
+impl From<crate::ValidationError> for Error {
+    fn from(err: crate::ValidationError) -> Self {
+        Error::Validation(err)
+    }
+}
@@ -0,0 +1,327 @@
+//! Writing of the generated files.
+
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::header::Fnv;
+use crate::{Error, Result};
+
+/// How generated files are written.
+///
+/// Files are always written atomically: content goes to a temporary file next to the
+/// target, which is then renamed over it, so an interrupted build never leaves a
+/// half-written file behind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum WriteMode {
+    /// Always replace the file.
+    #[default]
+    Always,
+    /// Leave the file untouched if its content is already the generated one.
+    ///
+    /// This keeps its modification time, so crates `include!`ing it aren't rebuilt
+    /// every time the build script runs.
+    IfChanged,
+}
+
+/// Write `content` to `target` according to `mode`, returns whether it was written.
+pub(crate) fn write_file(target: &Path, content: &[u8], mode: WriteMode) -> io::Result<bool> {
+    if mode == WriteMode::IfChanged && is_unchanged(target, content)? {
+        return Ok(false);
+    }
+    let temp = temp_path(target);
+    let res = File::create(&temp)
+        .and_then(|mut file| file.write_all(content))
+        .and_then(|_| fs::rename(&temp, target));
+    if res.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    res.map(|_| true)
+}
+
+/// Binary files written next to a generated file and included with `include_bytes!`.
+///
+/// Files are named after the generated one and the hash of their content, `tables.rs`
+/// having `tables.5cd5e1d9a2d8a3b4.bin`, etc. An existing file is thus never given another
+/// content, and the previous version of the generated file keeps including the right data
+/// until it's replaced. The names are listed in `tables.rs.sidecars`, so that the files
+/// the new version doesn't include are removed once it's written, and only them. If it
+/// isn't written, the files created by this run are removed when [Sidecars] is dropped.
+pub(crate) struct Sidecars {
+    target: PathBuf,
+    mode: WriteMode,
+    /// Names, hashes and lengths of the files of this run.
+    files: Vec<(String, u64, u64)>,
+    /// Files of this run which didn't exist before.
+    created: Vec<PathBuf>,
+}
+
+impl Sidecars {
+    pub(crate) fn new(target: &Path, mode: WriteMode) -> Self {
+        Sidecars {
+            target: target.to_path_buf(),
+            mode,
+            files: Vec::new(),
+            created: Vec::new(),
+        }
+    }
+
+    /// Write a file containing `bytes`, returns its path relative to the generated file.
+    ///
+    /// The same content is only written once.
+    pub(crate) fn add(&mut self, bytes: &[u8]) -> io::Result<String> {
+        let hash = Fnv::hash(bytes);
+        let len = bytes.len() as u64;
+        let name = format!("{}.{:016x}.bin", self.stem(), hash);
+        let path = self.target.with_file_name(&name);
+        // the content isn't kept, the file already written is compared instead
+        if let Some((_, _, l)) = self.files.iter().find(|(_, h, _)| *h == hash) {
+            if *l == len && is_unchanged(&path, bytes)? {
+                return Ok(name);
+            }
+            return Err(io::Error::other(format!(
+                "another content has the hash of sidecar file {}",
+                name
+            )));
+        }
+        let existed = path.try_exists()?;
+        write_file(&path, bytes, self.mode)?;
+        if !existed {
+            self.created.push(path);
+        }
+        self.files.push((name.clone(), hash, len));
+        Ok(name)
+    }
+
+    /// Update the list of files once the generated file is written, and remove the ones
+    /// of the previous version which are not included anymore.
+    pub(crate) fn finish(mut self) -> io::Result<()> {
+        // the generated file includes them now
+        self.created.clear();
+        let mut list = self.target.file_name().unwrap_or_default().to_os_string();
+        list.push(".sidecars");
+        let list = self.target.with_file_name(list);
+        let previous = match fs::read_to_string(&list) {
+            Ok(previous) => previous,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+        let mut names: Vec<_> = self
+            .files
+            .iter()
+            .map(|(name, _, _)| name.as_str())
+            .collect();
+        names.sort_unstable();
+        if names.is_empty() {
+            remove_if_exists(&list)?;
+        } else {
+            write_file(
+                &list,
+                format!("{}\n", names.join("\n")).as_bytes(),
+                self.mode,
+            )?;
+        }
+        let stem = self.stem();
+        // only names of sidecar files are trusted, not paths
+        let is_sidecar = |name: &str| {
+            name.strip_prefix(&stem)
+                .and_then(|name| name.strip_prefix('.'))
+                .and_then(|name| name.strip_suffix(".bin"))
+                .is_some_and(|hash| hash.len() == 16 && hash.bytes().all(is_hex))
+        };
+        for name in previous.lines() {
+            if is_sidecar(name) && names.binary_search(&name).is_err() {
+                remove_if_exists(&self.target.with_file_name(name))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn stem(&self) -> String {
+        let stem = self.target.file_stem().unwrap_or_default();
+        stem.to_string_lossy().into_owned()
+    }
+}
+
+fn is_hex(byte: u8) -> bool {
+    byte.is_ascii_hexdigit()
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// Write the output of `write` to `target` according to `mode`, returns whether it was
+/// written.
+///
+/// The content is streamed to the temporary file, and only compared to the existing file
+/// afterwards with [WriteMode::IfChanged], so it's never held in memory.
+pub(crate) fn write_file_with(
+    target: &Path,
+    mode: WriteMode,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<()>,
+) -> Result<bool> {
+    let temp = temp_path(target);
+    let res = File::create(&temp)
+        .map_err(Error::from)
+        .and_then(|file| {
+            let mut out = BufWriter::new(file);
+            write(&mut out)?;
+            out.into_inner().map_err(|err| err.into_error())?;
+            Ok(())
+        })
+        .and_then(|_| match mode {
+            WriteMode::IfChanged => Ok(files_equal(&temp, target)?),
+            WriteMode::Always => Ok(false),
+        })
+        .and_then(|unchanged| {
+            if !unchanged {
+                fs::rename(&temp, target)?;
+            }
+            Ok(!unchanged)
+        });
+    // still there if it failed or the file is unchanged
+    let _ = fs::remove_file(&temp);
+    res
+}
+
+/// Temporary file next to a generated file, holding output until what precedes it in the
+/// generated file is known. It's removed when dropped.
+pub(crate) struct ScratchFile {
+    path: PathBuf,
+}
+
+impl ScratchFile {
+    pub(crate) fn new(target: &Path) -> Self {
+        ScratchFile {
+            path: temp_path(target),
+        }
+    }
+
+    /// Stream the output of `write` to the file.
+    pub(crate) fn write<T>(
+        &self,
+        write: impl FnOnce(&mut dyn fmt::Write) -> Result<T>,
+    ) -> Result<T> {
+        let mut file = BufWriter::new(File::create(&self.path)?);
+        let mut out = IoWriter::new(&mut file);
+        let res = write(&mut out);
+        let res = out.finish(res)?;
+        file.into_inner().map_err(|err| err.into_error())?;
+        Ok(res)
+    }
+
+    /// Copy the content of the file to `out`.
+    pub(crate) fn copy_to(&self, out: &mut dyn fmt::Write) -> Result<()> {
+        let mut file = BufReader::new(File::open(&self.path)?);
+        let mut line = String::new();
+        while file.read_line(&mut line)? != 0 {
+            out.write_str(&line)?;
+            line.clear();
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Sidecars {
+    fn drop(&mut self) {
+        for path in &self.created {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+impl Drop for ScratchFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Adapter writing `fmt::Write` output to an `io::Write`, keeping the I/O error which is
+/// lost by `fmt::Error`.
+pub(crate) struct IoWriter<'a> {
+    out: &'a mut dyn io::Write,
+    error: Option<io::Error>,
+}
+
+impl<'a> IoWriter<'a> {
+    pub(crate) fn new(out: &'a mut dyn io::Write) -> Self {
+        IoWriter { out, error: None }
+    }
+
+    /// Replace the `fmt::Error` of `res` by the I/O error which caused it.
+    pub(crate) fn finish<T>(self, res: Result<T>) -> Result<T> {
+        match (res, self.error) {
+            (Err(Error::Fmt(_)), Some(err)) => Err(Error::Io(err)),
+            (res, _) => res,
+        }
+    }
+}
+
+impl fmt::Write for IoWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.out.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+fn files_equal(a: &Path, b: &Path) -> io::Result<bool> {
+    let (a, b) = match (File::open(a), File::open(b)) {
+        (Ok(a), Ok(b)) => (a, b),
+        (_, Err(err)) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+        (Err(err), _) | (_, Err(err)) => return Err(err),
+    };
+    if a.metadata()?.len() != b.metadata()?.len() {
+        return Ok(false);
+    }
+    let (mut a, mut b) = (BufReader::new(a), BufReader::new(b));
+    let (mut buf_a, mut buf_b) = ([0; 8192], [0; 8192]);
+    loop {
+        let len = a.read(&mut buf_a)?;
+        if len == 0 {
+            return Ok(true);
+        }
+        b.read_exact(&mut buf_b[..len])?;
+        if buf_a[..len] != buf_b[..len] {
+            return Ok(false);
+        }
+    }
+}
+
+fn is_unchanged(target: &Path, content: &[u8]) -> io::Result<bool> {
+    let file = match File::open(target) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err),
+    };
+    if file.metadata()?.len() != content.len() as u64 {
+        return Ok(false);
+    }
+    let mut file = BufReader::new(file);
+    let mut buf = [0; 8192];
+    for chunk in content.chunks(buf.len()) {
+        let buf = &mut buf[..chunk.len()];
+        file.read_exact(buf)?;
+        if buf != chunk {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Path of a temporary file next to `target`, unique to the process and the call, so
+/// that concurrent writes, e.g. from threads of the build script, don't collide.
+fn temp_path(target: &Path) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let mut name = target.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.{}.tmp", std::process::id(), count));
+    target.with_file_name(name)
+}
@@ -0,0 +1,195 @@
+//! Header comment of generated files.
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+
+use crate::Result;
+
+/// Start of the line holding the content hash, followed by 16 hex digits.
+const HASH_PREFIX: &str = "// content-hash: fnv1a64:";
+
+/// Comment written at the top of generated files, enabled by default.
+///
+/// It marks the file as `@generated`, which rustfmt and code review tools recognise, and
+/// tells which version of constuneval and which crate generated it. Its last line is a
+/// hash of the rest of the file, checked by [verify()][Header::verify]:
+///
+/// ```text
+/// // @generated by constuneval 0.1.0 from the build script of tables 0.2.0, do not edit.
+/// // content-hash: fnv1a64:5cd5e1d9a2d8a3b4
+///
+/// const SQUARES: [u32; 4] = [...];
+/// ```
+///
+/// The header only contains `//` comments, as inner attributes such as `#![allow(...)]`
+/// and `//!` comments are rejected in files included with `include!`: attributes have to
+/// be put on the items, with [Config::attr][crate::Config::attr]. It doesn't change
+/// between builds, so it doesn't defeat [WriteMode::IfChanged][crate::WriteMode].
+///
+/// The hash doesn't cover the sidecar files written with
+/// [Config::sidecar_threshold][crate::Config::sidecar_threshold].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Header {
+    enabled: bool,
+    notes: Vec<String>,
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        Header::new()
+    }
+}
+
+impl Header {
+    /// The default header.
+    pub fn new() -> Self {
+        Header {
+            enabled: true,
+            notes: Vec::new(),
+        }
+    }
+
+    /// No header, the file starts with the generated items.
+    pub fn none() -> Self {
+        Header {
+            enabled: false,
+            notes: Vec::new(),
+        }
+    }
+
+    /// Add a line to the header, e.g. the command regenerating the file.
+    ///
+    /// `note` may contain many lines.
+    pub fn note(mut self, note: &str) -> Self {
+        self.notes.extend(note.lines().map(str::to_string));
+        self
+    }
+
+    /// Whether `code` starts with a header whose hash matches the rest of the code, i.e.
+    /// it wasn't edited since it was generated.
+    ///
+    /// ```
+    /// use constuneval::{ConstWriter, Header};
+    ///
+    /// let code = ConstWriter::new("unused.rs").item("A", &1_u8, None).render()?;
+    /// assert!(Header::verify(&code));
+    /// assert!(!Header::verify(&code.replace("= 1", "= 2")));
+    /// # Ok::<(), constuneval::Error>(())
+    /// ```
+    pub fn verify(code: &str) -> bool {
+        let mut rest = code;
+        while let Some(end) = rest.find('\n') {
+            let line = &rest[..end];
+            rest = &rest[end + 1..];
+            if let Some(hash) = line.strip_prefix(HASH_PREFIX) {
+                return hash.len() == 16
+                    && u64::from_str_radix(hash, 16) == Ok(Fnv::hash(rest.as_bytes()));
+            }
+            if !line.starts_with("//") {
+                break;
+            }
+        }
+        false
+    }
+
+    /// Text of the header, ending with an empty line.
+    fn text(&self, hash: u64) -> String {
+        let mut text = format!("// @generated by constuneval {}", env!("CARGO_PKG_VERSION"));
+        if let (Ok(name), Ok(version)) = (env::var("CARGO_PKG_NAME"), env::var("CARGO_PKG_VERSION"))
+        {
+            let from = if crate::is_build_script() {
+                " from the build script of"
+            } else {
+                " from"
+            };
+            text.push_str(&format!("{} {} {}", from, name, version));
+        }
+        text.push_str(", do not edit.\n");
+        for note in &self.notes {
+            match note.as_str() {
+                "" => text.push_str("//\n"),
+                note => text.push_str(&format!("// {}\n", note)),
+            }
+        }
+        text.push_str(&format!("{}{:016x}\n\n", HASH_PREFIX, hash));
+        text
+    }
+
+    /// Prepend the header to `code`.
+    pub(crate) fn prepend(&self, code: String) -> String {
+        if !self.enabled {
+            return code;
+        }
+        // the hash covers the empty line ending the header
+        let hash = Fnv::hash(format!("\n{}", code).as_bytes());
+        self.text(hash) + &code
+    }
+
+    /// Write the header followed by the output of `write` to `out`.
+    ///
+    /// The code is streamed, the hash is written over a placeholder once it's known.
+    pub(crate) fn write(
+        &self,
+        out: &mut BufWriter<File>,
+        write: impl FnOnce(&mut dyn io::Write) -> Result<()>,
+    ) -> Result<()> {
+        if !self.enabled {
+            return write(out);
+        }
+        let text = self.text(0);
+        let start = out.stream_position()?;
+        out.write_all(text.as_bytes())?;
+        let mut hashed = HashWriter {
+            out: &mut *out,
+            hash: Fnv::new(),
+        };
+        hashed.hash.write(b"\n");
+        write(&mut hashed)?;
+        let hash = hashed.hash.0;
+        // the placeholder is followed by "\n\n"
+        let offset = start + text.len() as u64 - 18;
+        out.seek(SeekFrom::Start(offset))?;
+        write!(out, "{:016x}", hash)?;
+        Ok(())
+    }
+}
+
+/// FNV-1a hash, 64 bits.
+pub(crate) struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub(crate) fn hash(bytes: &[u8]) -> u64 {
+        let mut hash = Fnv::new();
+        hash.write(bytes);
+        hash.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+/// Writer hashing what goes through it.
+struct HashWriter<'a> {
+    out: &'a mut dyn io::Write,
+    hash: Fnv,
+}
+
+impl io::Write for HashWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.out.write(buf)?;
+        self.hash.write(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
@@ -1,43 +1,73 @@
-//! Simple `Cow` focussed serializer for generating const Rust code using Debug trait.
+//! Simple `Cow` focussed serializer for generating const Rust code.
 //!
 //! ## Usage
 //! In general, to embed some code(tables/struct) into crate, you have to use the build script
 //! and [`include!`][include] macro. Inside the build script, you'll generate
 //! some code with one of the [to_file()][to_file], [to_string()][to_string]
-//! provided by `constuneval`,
+//! provided by `constuneval` (or [ConstWriter] to put many items in one file),
 //! and then include the generated file, like this:
 //! ```ignore
 //! include!(concat!(env!(OUT_DIR), "/file_name.rs"));
+//! // or
+//! constuneval::include_generated!("file_name.rs");
 //! ```
 //!
 //! Also this crate provides a fork of [UnevalCow]
-//! but with better serialization though Debug trait
+//! but with better serialization though [Uneval] trait
 //!
 //! ## How does it work?
 //!
-//! To keep things simple all the formatting/serialization is done with help
-//! of Debug trait. For most types such as `struct`, `enum`, `Vec`, etc it
-//! works fine, but not for `Deref` like types like `Cow` as their Debug essentially
-//! deref before formatting. To address this crate also provides [UnevalCow] as a
-//! substitute to [std::borrow::Cow].
+//! Values are serialized with the [Uneval] trait, whose implementations write a Rust
+//! expression constructing the value through an [Emitter]. It is implemented for
+//! primitives, tuples, arrays, slices, `Option`, `Result`, `&str`, `Vec`, `String`, maps,
+//! sets and [UnevalCow], and can be implemented for your own types in the same way as
+//! `Debug`. With the `derive` feature enabled, `#[derive(Uneval)]` generates the
+//! implementation for structs and enums (see
+//! [constuneval-derive](https://docs.rs/constuneval-derive) for its attributes).
+//!
+//! `Deref` like types such as `Cow` can't be constructed in const context when owned,
+//! which is why this crate provides [UnevalCow] as a substitute to [std::borrow::Cow]:
+//! it is always emitted as `::constuneval::UnevalCow::Borrowed` (see [Config::cow_path]).
+//! For the same reason, `Vec` and `String` values are emitted as `&'static [T]` slices and
+//! `&'static str` literals, unless wrapped in [Owned], which emits empty ones as
+//! `Vec::new()` and `String::new()`. Nothing is wrapped implicitly: `#[derive(Uneval)]`
+//! rejects the fields holding them unless told how to emit them. Maps and sets are emitted as
+//! `&'static [(K, V)]` and `&'static [T]` slices sorted by key, so the generated code is
+//! the same at every run. Byte slices are emitted as byte strings (see
+//! [Config::byte_strings]), or moved to a sidecar file included with `include_bytes!` above
+//! [Config::sidecar_threshold], and can be wrapped in [Aligned] to be reinterpreted
+//! without copying.
+//! Lookup tables can be emitted as a [ConstMap], a map over a sorted slice, or wrapped in
+//! [Phf] to be emitted as a [PhfMap] with a perfect hash function computed at build time.
+//!
+//! Types with a `Debug` implementation printing valid Rust can still be serialized the
+//! old way by wrapping them in [ViaDebug].
 //!
 //! Of course, we can't always directly construct the code for the desired value (more on this
 //! in the [Limitations](#limitations) section below).
 //!
 //! ## Example
 //! ```no_run
-//! use constuneval::{to_file, to_string, UnevalCow};
-//! use std::fmt;
+//! use constuneval::{to_file, Emitter, Result, Uneval, UnevalCow};
 //!
-//! #[derive(Debug)]
 //! pub struct FftDomain<F>
 //! where
-//!     [F]: 'static + ToOwned + fmt::Debug,
-//!     <[F] as std::borrow::ToOwned>::Owned: fmt::Debug,
+//!     [F]: 'static + ToOwned,
 //! {
 //!     pub some_table: UnevalCow<'static, [UnevalCow<'static, [F]>]>,
 //! }
 //!
+//! impl<F: Uneval> Uneval for FftDomain<F>
+//! where
+//!     [F]: 'static + ToOwned,
+//! {
+//!     fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+//!         e.struct_expr("FftDomain")
+//!             .field("some_table", &self.some_table)
+//!             .finish()
+//!     }
+//! }
+//!
 //! // some build time generated struct table
 //! let fft_temp = FftDomain {
 //!     some_table: UnevalCow::Owned(vec![
@@ -63,42 +93,120 @@
 //! content of `const_fft_tables.rs` (after running rustfmt on it)
 //! ```ignore
 //! const FFT_TABLE: FftDomain<'static, i32> = FftDomain {
-//!     some_table: UnevalCow::Borrowed(&[
-//!         UnevalCow::Borrowed(&[1, 2, 3, 4, 5]),
-//!         UnevalCow::Borrowed(&[1, 2, 3, 4, 5]),
-//!         UnevalCow::Borrowed(&[1, 2, 3, 4, 5]),
-//!         UnevalCow::Borrowed(&[1, 2, 3, 4, 5]),
-//!         UnevalCow::Borrowed(&[1, 2, 3, 4, 5]),
-//!         UnevalCow::Borrowed(&[1, 2, 3, 4, 5]),
+//!     some_table: ::constuneval::UnevalCow::Borrowed(&[
+//!         ::constuneval::UnevalCow::Borrowed(&[1, 2, 3, 4, 5]),
+//!         ::constuneval::UnevalCow::Borrowed(&[1, 2, 3, 4, 5]),
+//!         ::constuneval::UnevalCow::Borrowed(&[1, 2, 3, 4, 5]),
+//!         ::constuneval::UnevalCow::Borrowed(&[1, 2, 3, 4, 5]),
+//!         ::constuneval::UnevalCow::Borrowed(&[1, 2, 3, 4, 5]),
+//!         ::constuneval::UnevalCow::Borrowed(&[1, 2, 3, 4, 5]),
 //!     ]),
 //! };
 //! ```
 //!
-//! Now this file/code can be embed into crate using [`include!`][include] macro.
+//! Now this file/code can be embed into crate using [`include!`][include] macro. The
+//! types it refers to by name have to be in scope where it's included, or imported in a
+//! module with [Config::module] and [ConstWriter::module].
 //!
 //! ## Limitations
 //! There are some cases when `constuneval` will be unable to generate valid code. Namely:
-//! 1. This serializer is intended for use with types with well implemented Debug trait. It may not
-//! work if Debug trait is producing invalid outputs.
-//! Using `UnevalCow` with refrence types (like `UnevalCow<&T>`) is not supported for now.
-//! See [this](https://github.com/not-yet-awesome-rust/not-yet-awesome-rust/issues/93#issuecomment-782808921)
-//! for full explanation.
+//! 1. Values wrapped in [ViaDebug] are serialized with their Debug trait. It may not
+//!    work if Debug trait is producing invalid outputs.
+//! 2. Using `UnevalCow` with refrence types (like `UnevalCow<&T>`) is not supported for now.
+//!    See [this](https://github.com/not-yet-awesome-rust/not-yet-awesome-rust/issues/93#issuecomment-782808921)
+//!    for full explanation.
 //!
 //! [include]: https://doc.rust-lang.org/stable/std/macro.include.html
 
-use std::fmt;
-use std::fs::File;
-use std::io;
-use std::io::prelude::*;
-
+mod aligned;
+mod cargo;
+mod config;
+mod const_map;
+mod emitter;
+mod error;
+mod file;
+mod header;
+mod phf;
+mod ty;
+mod uneval;
 mod uneval_cow;
+#[cfg(feature = "validate")]
+mod validate;
+mod writer;
 
+pub use aligned::Aligned;
+pub use cargo::{is_build_script, out_path, rerun_if_changed, rerun_if_env_changed, warning};
+pub use config::{Config, ItemKind, Radix, RestrictedPath, Style, Visibility};
+pub use const_map::ConstMap;
+pub use emitter::{ArrayExpr, Emitter, StructExpr, TupleExpr};
+pub use error::{Error, Result};
+pub use file::WriteMode;
+pub use header::Header;
+pub use phf::{Phf, PhfHash, PhfHasher, PhfMap};
+pub use uneval::{Owned, Uneval, ViaDebug};
 pub use uneval_cow::UnevalCow;
+#[cfg(feature = "validate")]
+pub use validate::{validate, ValidationError};
+pub use writer::ConstWriter;
+
+#[cfg(feature = "derive")]
+pub use constuneval_derive::Uneval;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::uneval::UnevalFn;
+}
 
 /// Obtain string with generated const Rust code.
-pub fn to_string<T: fmt::Debug>(name: &str, value: &T, ty: Option<&str>) -> String {
-    let type_name = ty.unwrap_or(std::any::type_name::<T>());
-    return format!("const {}: {} = {:#?};", name, type_name, value);
+///
+/// # Panics
+/// If generating the code fails, see [try_to_string()][try_to_string].
+///
+/// Use [Config::to_string] to emit something else than a private `const` item. When `ty`
+/// is `None`, the type of the item is inferred with [Uneval::const_type].
+pub fn to_string<T: Uneval + ?Sized>(name: &str, value: &T, ty: Option<&str>) -> String {
+    Config::default().to_string(name, value, ty)
+}
+
+/// Obtain string with generated const Rust code, or the reason why it can't be generated.
+///
+/// ```
+/// use constuneval::{try_to_string, Error, Owned};
+///
+/// let table = [("empty", Owned(vec![])), ("full", Owned(vec![1_u8]))];
+/// match try_to_string("TABLE", &table, None) {
+///     Err(Error::Unsupported { path, .. }) => assert_eq!(path, "TABLE[1].1"),
+///     _ => unreachable!(),
+/// }
+/// ```
+pub fn try_to_string<T: Uneval + ?Sized>(
+    name: &str,
+    value: &T,
+    ty: Option<&str>,
+) -> Result<String> {
+    Config::default().try_to_string(name, value, ty)
+}
+
+/// Generate the const Rust code into `out`.
+///
+/// The code is streamed to `out` through a [std::io::BufWriter] as it is generated,
+/// instead of being built in memory first, except with the `validate` feature which
+/// needs the whole code to parse it. Part of the code may have been written if
+/// generating it fails.
+///
+/// ```
+/// let mut out = Vec::new();
+/// constuneval::to_writer(&mut out, "TABLE", &[1_u16, 2], None)?;
+/// assert_eq!(out, b"const TABLE: [u16; 2] = [\n    1,\n    2,\n];\n");
+/// # Ok::<(), constuneval::Error>(())
+/// ```
+pub fn to_writer<T: Uneval + ?Sized>(
+    out: impl std::io::Write,
+    name: &str,
+    value: &T,
+    ty: Option<&str>,
+) -> Result<()> {
+    Config::default().to_writer(out, name, value, ty)
 }
 
 /// Generate the const Rust code and write it to temporary file
@@ -122,14 +230,90 @@ pub fn to_string<T: fmt::Debug>(name: &str, value: &T, ty: Option<&str>) -> Stri
 /// include!(concat!(env!(OUT_DIR), "/file_name.rs"));
 /// ```
 ///
+/// [to_out_dir()][to_out_dir] and [include_generated!] do both steps for you.
+///
+/// The code is streamed to the file as with [to_writer()][to_writer], but the file is only
+/// replaced once it's complete. It starts with a comment telling it's generated, see
+/// [Header].
+///
+/// Use [Config::to_file] to emit something else than a private `const` item, e.g. a
+/// `static` for big tables.
+///
 /// [include]: https://doc.rust-lang.org/stable/std/macro.include.html
-pub fn to_file<T: fmt::Debug>(
+pub fn to_file<T: Uneval + ?Sized>(
+    target: impl AsRef<std::path::Path>,
+    name: &str,
+    value: &T,
+    ty: Option<&str>,
+) -> Result<()> {
+    Config::default().to_file(target, name, value, ty)
+}
+
+/// Generate a `&'static [T]` item from the elements of `iter` and write it to `target`.
+///
+/// Elements are emitted as they are produced, so the table is never collected into
+/// memory. `elem_ty` is the type of the elements, inferred if `None`. Use
+/// [to_file_array()][to_file_array] for a `[T; N]` item.
+///
+/// ```no_run
+/// let crc_table = (0..256_u32).map(|mut crc| {
+///     for _ in 0..8 {
+///         crc = if crc & 1 == 1 { 0xedb8_8320 ^ (crc >> 1) } else { crc >> 1 };
+///     }
+///     crc
+/// });
+/// constuneval::to_file_iter("crc.rs", "CRC_TABLE", crc_table, None)?;
+/// # Ok::<(), constuneval::Error>(())
+/// ```
+pub fn to_file_iter<I>(
     target: impl AsRef<std::path::Path>,
     name: &str,
+    iter: I,
+    elem_ty: Option<&str>,
+) -> Result<()>
+where
+    I: IntoIterator,
+    I::Item: Uneval,
+{
+    Config::default().to_file_iter(target, name, iter, elem_ty)
+}
+
+/// Generate a `[T; N]` item from the elements of `iter` and write it to `target`.
+///
+/// Same as [to_file_iter()][to_file_iter], `N` being the number of elements, which doesn't
+/// have to be known in advance: they are streamed to a temporary file next to `target`
+/// until it is.
+pub fn to_file_array<I>(
+    target: impl AsRef<std::path::Path>,
+    name: &str,
+    iter: I,
+    elem_ty: Option<&str>,
+) -> Result<()>
+where
+    I: IntoIterator,
+    I::Item: Uneval,
+{
+    Config::default().to_file_array(target, name, iter, elem_ty)
+}
+
+/// Generate the const Rust code into `file_name` in the `OUT_DIR` of the build script.
+///
+/// Fails with [Error::NotBuildScript] if not called from a build script.
+///
+/// In `build.rs`:
+/// ```no_run
+/// # let value = 1_u8;
+/// constuneval::to_out_dir("file_name.rs", "MYVAR", &value, Some("u8")).expect("Write failed");
+/// ```
+/// and in the crate:
+/// ```ignore
+/// constuneval::include_generated!("file_name.rs");
+/// ```
+pub fn to_out_dir<T: Uneval + ?Sized>(
+    file_name: &str,
+    name: &str,
     value: &T,
     ty: Option<&str>,
-) -> Result<(), io::Error> {
-    let mut file = File::create(target)?;
-    file.write_all(to_string(name, value, ty).as_bytes())?;
-    Ok(())
+) -> Result<()> {
+    Config::default().to_out_dir(file_name, name, value, ty)
 }
@@ -0,0 +1,354 @@
+//! Perfect hash maps generated at build time.
+//!
+//! [Phf] computes a perfect hash function for the keys of a map with the CHD algorithm
+//! (hash, displace and compress) and emits a [PhfMap], which finds the entry of a key
+//! with a single hash and no comparison but the final one.
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::uneval::{uneval_map, UnevalFn};
+use crate::{Emitter, Result, Uneval};
+
+/// Average number of keys per displacement bucket.
+const LAMBDA: usize = 5;
+
+/// Number of hash keys tried before giving up.
+const MAX_ATTEMPTS: u64 = 1024;
+
+/// Number of `d1` displacements tried for a bucket, each with every `d2`, before trying
+/// another hash key.
+const MAX_D1: u32 = 32;
+
+/// Map with a perfect hash function, generated at build time by [Phf].
+///
+/// Its data is stored in `'static` slices and it is created with a `const fn`, so it can
+/// be a `const` or `static` item.
+pub struct PhfMap<K: 'static, V: 'static> {
+    key: u64,
+    disps: &'static [(u32, u32)],
+    entries: &'static [(K, V)],
+}
+
+impl<K, V> PhfMap<K, V> {
+    /// Create a map from the hash key, displacements and entries computed by [Phf].
+    ///
+    /// It isn't meant to be called directly, the map is broken if the arguments don't come
+    /// from [Phf].
+    pub const fn new(key: u64, disps: &'static [(u32, u32)], entries: &'static [(K, V)]) -> Self {
+        PhfMap {
+            key,
+            disps,
+            entries,
+        }
+    }
+
+    /// Number of entries in the map.
+    pub const fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the map is empty.
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Value corresponding to `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        Q: PhfHash + Eq + ?Sized,
+        K: Borrow<Q>,
+    {
+        self.get_entry(key).map(|(_, value)| value)
+    }
+
+    /// Whether the map contains `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        Q: PhfHash + Eq + ?Sized,
+        K: Borrow<Q>,
+    {
+        self.get_entry(key).is_some()
+    }
+
+    /// Entry corresponding to `key`.
+    pub fn get_entry<Q>(&self, key: &Q) -> Option<&(K, V)>
+    where
+        Q: PhfHash + Eq + ?Sized,
+        K: Borrow<Q>,
+    {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let hashes = Hashes::new(key, self.key);
+        let (d1, d2) = self.disps[(hashes.g % self.disps.len() as u32) as usize];
+        let entry = &self.entries[hashes.index(d1, d2, self.entries.len())];
+        if entry.0.borrow() == key {
+            Some(entry)
+        } else {
+            None
+        }
+    }
+
+    /// Iterator over the entries of the map, in no particular order.
+    pub fn entries(&self) -> std::slice::Iter<'_, (K, V)> {
+        self.entries.iter()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for PhfMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.entries.iter().map(|(k, v)| (k, v)))
+            .finish()
+    }
+}
+
+/// Types which can be the keys of a [PhfMap].
+///
+/// The hash must be the same for the type used when generating the map and the one of
+/// the keys in the generated code, e.g. `String` and `str` hash the same bytes.
+pub trait PhfHash {
+    /// Feed the value into `state`.
+    fn phf_hash(&self, state: &mut PhfHasher);
+}
+
+/// Deterministic hasher used by [PhfHash] implementations.
+///
+/// Unlike [std::collections::hash_map::DefaultHasher], its output never changes between
+/// runs, platforms or versions of Rust, as the generated code relies on it.
+pub struct PhfHasher(u64);
+
+impl PhfHasher {
+    /// Feed `bytes` into the hasher.
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+/// Hash of a key, split into a bucket and two hashes for the displacement.
+struct Hashes {
+    g: u32,
+    f1: u32,
+    f2: u32,
+}
+
+impl Hashes {
+    fn new<T: PhfHash + ?Sized>(value: &T, key: u64) -> Self {
+        let mut state = PhfHasher(0xcbf2_9ce4_8422_2325 ^ key);
+        value.phf_hash(&mut state);
+        let h1 = mix(state.0);
+        let h2 = mix(h1 ^ key);
+        Hashes {
+            g: (h1 >> 32) as u32,
+            f1: h1 as u32,
+            f2: h2 as u32,
+        }
+    }
+
+    fn index(&self, d1: u32, d2: u32, len: usize) -> usize {
+        (d2.wrapping_add(self.f1.wrapping_mul(d1))
+            .wrapping_add(self.f2)
+            % len as u32) as usize
+    }
+}
+
+/// splitmix64 finalizer.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+macro_rules! phf_hash_bytes {
+    ($($ty:ty),*) => {$(
+        impl PhfHash for $ty {
+            fn phf_hash(&self, state: &mut PhfHasher) {
+                state.write(&self.to_le_bytes());
+            }
+        }
+    )*};
+}
+
+phf_hash_bytes!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+/// Hashed as 64 bits, so that the map doesn't depend on the target of the build script.
+impl PhfHash for usize {
+    fn phf_hash(&self, state: &mut PhfHasher) {
+        (*self as u64).phf_hash(state)
+    }
+}
+
+/// Hashed as 64 bits, as `usize`.
+impl PhfHash for isize {
+    fn phf_hash(&self, state: &mut PhfHasher) {
+        (*self as i64).phf_hash(state)
+    }
+}
+
+impl PhfHash for char {
+    fn phf_hash(&self, state: &mut PhfHasher) {
+        u32::from(*self).phf_hash(state)
+    }
+}
+
+impl PhfHash for bool {
+    fn phf_hash(&self, state: &mut PhfHasher) {
+        u8::from(*self).phf_hash(state)
+    }
+}
+
+impl PhfHash for str {
+    fn phf_hash(&self, state: &mut PhfHasher) {
+        state.write(self.as_bytes())
+    }
+}
+
+impl PhfHash for String {
+    fn phf_hash(&self, state: &mut PhfHasher) {
+        self.as_str().phf_hash(state)
+    }
+}
+
+impl PhfHash for [u8] {
+    fn phf_hash(&self, state: &mut PhfHasher) {
+        state.write(self)
+    }
+}
+
+impl PhfHash for Vec<u8> {
+    fn phf_hash(&self, state: &mut PhfHasher) {
+        self.as_slice().phf_hash(state)
+    }
+}
+
+impl<T: PhfHash + ?Sized> PhfHash for &T {
+    fn phf_hash(&self, state: &mut PhfHasher) {
+        (**self).phf_hash(state)
+    }
+}
+
+/// Wrapper emitting a `HashMap` as a [PhfMap].
+///
+/// `PhfMap` is referred to by its full path, see
+/// [Config::crate_path][crate::Config::crate_path].
+///
+/// ```
+/// use constuneval::{to_string, Phf};
+/// use std::collections::HashMap;
+///
+/// let keywords: HashMap<_, _> = vec![("fn".to_string(), 1_u8), ("let".to_string(), 2)]
+///     .into_iter()
+///     .collect();
+/// let code = to_string("KEYWORDS", &Phf(&keywords), None);
+/// assert!(code.starts_with(
+///     "const KEYWORDS: ::constuneval::PhfMap<&'static str, u8> = ::constuneval::PhfMap::new("
+/// ));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Phf<T>(pub T);
+
+impl<K: Uneval + PhfHash, V: Uneval, S> Uneval for Phf<HashMap<K, V, S>> {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        Phf(&self.0).uneval(e)
+    }
+
+    fn const_type() -> Result<String> {
+        Phf::<&HashMap<K, V, S>>::const_type()
+    }
+}
+
+impl<K: Uneval + PhfHash, V: Uneval, S> Uneval for Phf<&HashMap<K, V, S>> {
+    /// Fails if no perfect hash function is found, which only happens if different keys
+    /// hash the same bytes.
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        let entries: Vec<_> = self.0.iter().collect();
+        let hash = match generate(&entries) {
+            Some(hash) => hash,
+            None => return Err(e.error("no perfect hash function found for the keys")),
+        };
+        let path = e.crate_path("PhfMap");
+        e.tuple_expr(&format!("{}::new", path))
+            .field(&hash.key)
+            .field(&hash.disps)
+            .field(&UnevalFn(|e: &mut Emitter<'_>| {
+                uneval_map(hash.order.iter().map(|&i| entries[i]), e)
+            }))
+            .finish()
+    }
+
+    fn const_type() -> Result<String> {
+        Ok(format!(
+            "::constuneval::PhfMap<{}, {}>",
+            K::const_type()?,
+            V::const_type()?
+        ))
+    }
+}
+
+/// Perfect hash function found for a set of keys.
+struct PerfectHash {
+    key: u64,
+    disps: Vec<(u32, u32)>,
+    /// Index of the entry stored in every slot of the map.
+    order: Vec<usize>,
+}
+
+fn generate<K: PhfHash, V>(entries: &[(K, V)]) -> Option<PerfectHash> {
+    if entries.is_empty() {
+        return Some(PerfectHash {
+            key: 0,
+            disps: Vec::new(),
+            order: Vec::new(),
+        });
+    }
+    (0..MAX_ATTEMPTS).find_map(|attempt| try_generate(entries, mix(attempt)))
+}
+
+fn try_generate<K: PhfHash, V>(entries: &[(K, V)], key: u64) -> Option<PerfectHash> {
+    let hashes: Vec<_> = entries.iter().map(|(k, _)| Hashes::new(k, key)).collect();
+    let buckets_len = entries.len().div_ceil(LAMBDA);
+    let mut buckets = vec![Vec::new(); buckets_len];
+    for (i, hash) in hashes.iter().enumerate() {
+        buckets[(hash.g % buckets_len as u32) as usize].push(i);
+    }
+    let mut bucket_order: Vec<_> = (0..buckets_len).collect();
+    bucket_order.sort_by_key(|&b| std::cmp::Reverse(buckets[b].len()));
+
+    let len = entries.len();
+    let mut order = vec![None; len];
+    let mut disps = vec![(0, 0); buckets_len];
+    // Generation at which every slot was tried for the current bucket, which avoids clearing
+    // the tried slots between each displacement.
+    let mut tried = vec![0_u64; len];
+    let mut generation = 0;
+    'buckets: for &b in &bucket_order {
+        for d1 in 0..MAX_D1.min(len as u32) {
+            for d2 in 0..len as u32 {
+                generation += 1;
+                let fits = buckets[b].iter().all(|&i| {
+                    let index = hashes[i].index(d1, d2, len);
+                    let free = order[index].is_none() && tried[index] != generation;
+                    tried[index] = generation;
+                    free
+                });
+                if fits {
+                    for &i in &buckets[b] {
+                        order[hashes[i].index(d1, d2, len)] = Some(i);
+                    }
+                    disps[b] = (d1, d2);
+                    continue 'buckets;
+                }
+            }
+        }
+        return None;
+    }
+    Some(PerfectHash {
+        key,
+        disps,
+        order: order.into_iter().map(Option::unwrap).collect(),
+    })
+}
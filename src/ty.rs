@@ -0,0 +1,113 @@
+//! Fallback for type inference based on [std::any::type_name].
+
+use crate::{Error, Result};
+
+/// Prelude types which don't need any path.
+const PRELUDE: &[&str] = &["Box", "Option", "Result", "String", "Vec"];
+
+/// Name of `T` as it can be written in the generated code.
+///
+/// Paths to `alloc` are replaced by `std`, prelude types lose their path and types of
+/// other crates are reduced to their name, as `type_name()` reports them relative to the
+/// crate generating the code (e.g. `build_script_build::Table`), which is not the one
+/// including it.
+pub(crate) fn type_name<T: ?Sized>() -> Result<String> {
+    let name = std::any::type_name::<T>();
+    if name.contains('{') {
+        return Err(Error::TypeInference(format!("`{}` can't be named", name)));
+    }
+
+    let (paths, rest) = split_paths(name);
+    let mut out = String::with_capacity(name.len());
+    for (before, segments) in paths {
+        out.push_str(before);
+        push_path(&mut out, &segments);
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Paths importing `T` and the types of its generic arguments in the crate including the
+/// generated code, leaving out the ones which don't need to be imported.
+///
+/// The crate of a build script is named `build_script_build` (after its file), its types
+/// are expected to be at the same place in the crate including the code. The types of
+/// constuneval are always emitted with their path.
+pub(crate) fn import_paths<T: ?Sized>() -> Vec<String> {
+    let name = std::any::type_name::<T>();
+    if name.contains('{') {
+        return Vec::new();
+    }
+    let (paths, _) = split_paths(name);
+    paths
+        .iter()
+        .filter_map(|(_, segments)| match segments.as_slice() {
+            [_] | ["alloc" | "core" | "std" | "constuneval", ..] => None,
+            [krate, rest @ ..] if krate.starts_with("build_script_") => {
+                Some(format!("crate::{}", rest.join("::")))
+            }
+            _ => Some(format!("::{}", segments.join("::"))),
+        })
+        .collect()
+}
+
+/// Names of the types `ty` refers to without a path, e.g. `Entry` in
+/// `&'static [Entry]`.
+pub(crate) fn named_types(ty: &str) -> Vec<&str> {
+    let (paths, _) = split_paths(ty);
+    paths
+        .into_iter()
+        .filter_map(|(_, segments)| match segments.as_slice() {
+            [name] => Some(*name),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Paths in the type `name` with the text preceding each of them, and the text following
+/// the last one.
+fn split_paths(name: &str) -> (Vec<(&str, Vec<&str>)>, &str) {
+    let mut paths = Vec::new();
+    let mut rest = name;
+    while let Some(start) = rest.find(is_ident_start) {
+        let before = &rest[..start];
+        rest = &rest[start..];
+        let mut segments = Vec::new();
+        loop {
+            let end = rest.find(|c: char| !is_ident(c)).unwrap_or(rest.len());
+            segments.push(&rest[..end]);
+            rest = &rest[end..];
+            match rest.strip_prefix("::") {
+                Some(next) if next.starts_with(is_ident_start) => rest = next,
+                _ => break,
+            }
+        }
+        paths.push((before, segments));
+    }
+    (paths, rest)
+}
+
+fn push_path(out: &mut String, segments: &[&str]) {
+    let last = segments[segments.len() - 1];
+    match segments[0] {
+        _ if segments.len() == 1 => out.push_str(last),
+        "alloc" | "core" | "std" if PRELUDE.contains(&last) => out.push_str(last),
+        "alloc" => {
+            out.push_str("std");
+            for segment in &segments[1..] {
+                out.push_str("::");
+                out.push_str(segment);
+            }
+        }
+        "core" | "std" => out.push_str(&segments.join("::")),
+        _ => out.push_str(last),
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
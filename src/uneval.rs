@@ -0,0 +1,600 @@
+//! The [Uneval] trait and its implementations for std types.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::{self, Write};
+
+use crate::{Emitter, Radix, Result, UnevalCow};
+
+/// Types which can be written out as a Rust expression constructing them.
+///
+/// Unlike `Debug`, whose output only *looks* like Rust for some types, an `Uneval`
+/// implementation must produce an expression which is valid in a `const` context and
+/// evaluates to an equal value. The emitted expression may use types different from
+/// `Self` when `Self` can't be built in const context, e.g. [UnevalCow] always emits
+/// `UnevalCow::Borrowed`.
+///
+/// # Example
+/// ```
+/// use constuneval::{Emitter, Result, Uneval};
+///
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// impl Uneval for Point {
+///     fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+///         e.struct_expr("Point")
+///             .field("x", &self.x)
+///             .field("y", &self.y)
+///             .finish()
+///     }
+/// }
+///
+/// assert_eq!(
+///     constuneval::to_string("ORIGIN", &Point { x: 0, y: 0 }, Some("Point")),
+///     "const ORIGIN: Point = Point {\n    x: 0,\n    y: 0,\n};"
+/// );
+/// ```
+pub trait Uneval {
+    /// Emit an expression constructing `self`.
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()>;
+
+    /// Emit an expression constructing a `&'static` reference to `self`.
+    ///
+    /// By default it's `&` followed by [uneval()][Uneval::uneval], types having a
+    /// literal which already is a reference (like `str`) override this.
+    fn uneval_ref(&self, e: &mut Emitter<'_>) -> Result<()> {
+        e.write_str("&")?;
+        self.uneval(e)
+    }
+
+    /// Emit an expression constructing a `&'static [Self]` slice, used by the
+    /// implementation for `[T]`.
+    ///
+    /// The default implementation emits `&[...]`, `u8` overrides it to emit a byte
+    /// string.
+    fn uneval_slice_ref(slice: &[Self], e: &mut Emitter<'_>) -> Result<()>
+    where
+        Self: Sized,
+    {
+        e.write_str("&")?;
+        e.array_expr().entries(slice).finish()
+    }
+
+    /// Rust type of the expression emitted by [uneval()][Uneval::uneval], used when no
+    /// type is given for an item.
+    ///
+    /// It's the type of the const value, which may differ from `Self`, e.g. `UnevalCow<[u8]>`
+    /// is `UnevalCow<'static, [u8]>`. The default implementation uses
+    /// [std::any::type_name], without the paths which can't be used from the crate
+    /// including the generated code. The types of constuneval are written
+    /// `::constuneval::Name`, which [Config::crate_path][crate::Config::crate_path] rewrites.
+    fn const_type() -> Result<String> {
+        crate::ty::type_name::<Self>()
+    }
+}
+
+impl Uneval for bool {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        Ok(write!(e, "{}", self)?)
+    }
+
+    fn const_type() -> Result<String> {
+        Ok("bool".to_string())
+    }
+}
+
+/// Write an integer literal with the radix, digit grouping and suffix of the
+/// configuration. `bits` is the width the digits are padded to, if any.
+fn write_int(
+    e: &mut Emitter<'_>,
+    negative: bool,
+    abs: u128,
+    bits: Option<u32>,
+    ty: &str,
+) -> Result<()> {
+    let config = e.config();
+    let (prefix, digits, digit_bits) = match config.radix {
+        Radix::Decimal => ("", abs.to_string(), None),
+        Radix::Hex => ("0x", format!("{:x}", abs), Some(4)),
+        Radix::Octal => ("0o", format!("{:o}", abs), Some(3)),
+        Radix::Binary => ("0b", format!("{:b}", abs), Some(1)),
+    };
+    let width = match (bits, digit_bits) {
+        (Some(bits), Some(digit_bits)) => bits.div_ceil(digit_bits) as usize,
+        _ => 0,
+    };
+    let digits = format!("{:0>w$}", digits, w = width);
+
+    let mut literal = String::from(if negative { "-" } else { "" });
+    literal.push_str(prefix);
+    for (i, digit) in digits.chars().enumerate() {
+        let remaining = digits.len() - i;
+        if i > 0 && config.digit_group > 0 && remaining % config.digit_group == 0 {
+            literal.push('_');
+        }
+        literal.push(digit);
+    }
+    if config.suffixes {
+        literal.push_str(ty);
+    }
+    Ok(e.write_str(&literal)?)
+}
+
+macro_rules! uneval_int {
+    ($($ty:ident($unsigned:ident, $padded:literal)),*) => {$(
+        impl Uneval for $ty {
+            #[allow(unused_comparisons)]
+            fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+                let negative = *self < 0;
+                let abs = if negative {
+                    (*self as $unsigned).wrapping_neg()
+                } else {
+                    *self as $unsigned
+                };
+                let bits = if $padded { Some($ty::BITS) } else { None };
+                write_int(e, negative, abs as u128, bits, stringify!($ty))
+            }
+
+            fn const_type() -> Result<String> {
+                Ok(stringify!($ty).to_string())
+            }
+        }
+    )*};
+}
+
+/// Slices of bytes are emitted as byte strings from 16 bytes, and as sidecar files with
+/// [Config::sidecar_threshold][crate::Config::sidecar_threshold], see [Emitter::emit_bytes].
+impl Uneval for u8 {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        write_int(e, false, u128::from(*self), Some(8), "u8")
+    }
+
+    fn uneval_slice_ref(slice: &[u8], e: &mut Emitter<'_>) -> Result<()> {
+        e.emit_bytes(slice)
+    }
+
+    fn const_type() -> Result<String> {
+        Ok("u8".to_string())
+    }
+}
+
+macro_rules! uneval_float {
+    ($($ty:ident),*) => {$(
+        impl Uneval for $ty {
+            /// Emits a literal, which round-trips exactly, or the `NAN`, `INFINITY` and
+            /// `NEG_INFINITY` constants. Other NaNs, and every value with
+            /// [Config::float_bits][crate::Config::float_bits], are emitted with `from_bits`.
+            fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+                let bits = self.to_bits();
+                if e.config().float_bits || self.is_nan() && bits != $ty::NAN.to_bits() {
+                    let width = 2 + 2 * std::mem::size_of::<$ty>();
+                    let ty = stringify!($ty);
+                    return Ok(write!(e, "{}::from_bits({:#0w$x})", ty, bits, w = width)?);
+                }
+                match *self {
+                    x if x.is_nan() => Ok(write!(e, "{}::NAN", stringify!($ty))?),
+                    x if x == $ty::INFINITY => Ok(write!(e, "{}::INFINITY", stringify!($ty))?),
+                    x if x == $ty::NEG_INFINITY => {
+                        Ok(write!(e, "{}::NEG_INFINITY", stringify!($ty))?)
+                    }
+                    x if e.config().suffixes => Ok(write!(e, "{:?}{}", x, stringify!($ty))?),
+                    x => Ok(write!(e, "{:?}", x)?),
+                }
+            }
+
+            fn const_type() -> Result<String> {
+                Ok(stringify!($ty).to_string())
+            }
+        }
+    )*};
+}
+
+uneval_int!(
+    i8(u8, true),
+    i16(u16, true),
+    i32(u32, true),
+    i64(u64, true),
+    i128(u128, true),
+    isize(usize, false),
+    u16(u16, true),
+    u32(u32, true),
+    u64(u64, true),
+    u128(u128, true),
+    usize(usize, false)
+);
+uneval_float!(f32, f64);
+
+impl Uneval for char {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        Ok(write!(e, "{:?}", self)?)
+    }
+
+    fn const_type() -> Result<String> {
+        Ok("char".to_string())
+    }
+}
+
+impl Uneval for str {
+    /// Emits a string literal, which already is a `&'static str`.
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        Ok(write!(e, "{:?}", self)?)
+    }
+
+    fn uneval_ref(&self, e: &mut Emitter<'_>) -> Result<()> {
+        self.uneval(e)
+    }
+
+    fn const_type() -> Result<String> {
+        Ok("str".to_string())
+    }
+}
+
+impl Uneval for () {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        Ok(e.write_str("()")?)
+    }
+
+    fn const_type() -> Result<String> {
+        Ok("()".to_string())
+    }
+}
+
+macro_rules! uneval_tuple {
+    ($($name:ident)+) => {
+        impl<$($name: Uneval),+> Uneval for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+                let ($($name,)+) = self;
+                e.tuple_expr("")$(.field($name))+.finish()
+            }
+
+            fn const_type() -> Result<String> {
+                let types = [$($name::const_type()?),+];
+                if types.len() == 1 {
+                    Ok(format!("({},)", types[0]))
+                } else {
+                    Ok(format!("({})", types.join(", ")))
+                }
+            }
+        }
+    };
+}
+
+uneval_tuple!(A);
+uneval_tuple!(A B);
+uneval_tuple!(A B C);
+uneval_tuple!(A B C D);
+uneval_tuple!(A B C D E);
+uneval_tuple!(A B C D E F);
+uneval_tuple!(A B C D E F G);
+uneval_tuple!(A B C D E F G H);
+uneval_tuple!(A B C D E F G H I);
+uneval_tuple!(A B C D E F G H I J);
+uneval_tuple!(A B C D E F G H I J K);
+uneval_tuple!(A B C D E F G H I J K L);
+
+impl<T: Uneval, const N: usize> Uneval for [T; N] {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        e.array_expr().entries(self).finish()
+    }
+
+    fn const_type() -> Result<String> {
+        Ok(format!("[{}; {}]", T::const_type()?, N))
+    }
+}
+
+impl<T: Uneval> Uneval for [T] {
+    /// Emits an array expression, so only [uneval_ref()][Uneval::uneval_ref] is
+    /// meaningful for slices.
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        e.array_expr().entries(self).finish()
+    }
+
+    fn uneval_ref(&self, e: &mut Emitter<'_>) -> Result<()> {
+        T::uneval_slice_ref(self, e)
+    }
+
+    fn const_type() -> Result<String> {
+        Ok(format!("[{}]", T::const_type()?))
+    }
+}
+
+impl<T: Uneval> Uneval for Option<T> {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        match self {
+            Some(value) => e.tuple_expr("Some").field(value).finish(),
+            None => Ok(e.write_str("None")?),
+        }
+    }
+
+    fn const_type() -> Result<String> {
+        Ok(format!("Option<{}>", T::const_type()?))
+    }
+}
+
+impl<T: Uneval, E: Uneval> Uneval for Result<T, E> {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        match self {
+            Ok(value) => e.tuple_expr("Ok").field(value).finish(),
+            Err(err) => e.tuple_expr("Err").field(err).finish(),
+        }
+    }
+
+    fn const_type() -> Result<String> {
+        Ok(format!(
+            "Result<{}, {}>",
+            T::const_type()?,
+            E::const_type()?
+        ))
+    }
+}
+
+impl<T: Uneval + ?Sized> Uneval for &T {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        (**self).uneval_ref(e)
+    }
+
+    fn const_type() -> Result<String> {
+        Ok(format!("&'static {}", T::const_type()?))
+    }
+}
+
+impl<B> Uneval for UnevalCow<'_, B>
+where
+    B: Uneval + ToOwned + ?Sized,
+{
+    /// Always emits `UnevalCow::Borrowed`, whether the value is owned or not, with the path
+    /// set by [Config::cow_path][crate::Config::cow_path].
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        let path = e.crate_path("UnevalCow");
+        e.tuple_expr(&format!("{}::Borrowed", path))
+            .field(&&**self)
+            .finish()
+    }
+
+    fn const_type() -> Result<String> {
+        Ok(format!(
+            "::constuneval::UnevalCow<'static, {}>",
+            B::const_type()?
+        ))
+    }
+}
+
+/// Emitted as a `&'static [T]` slice, the only way to construct a non-empty `Vec` content
+/// in const context. Use [Owned] when the item needs an actual `Vec`.
+impl<T: Uneval> Uneval for Vec<T> {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        self.as_slice().uneval_ref(e)
+    }
+
+    fn const_type() -> Result<String> {
+        Ok(format!("&'static [{}]", T::const_type()?))
+    }
+}
+
+/// Emitted as a `&'static str` literal. Use [Owned] when the item needs an actual
+/// `String`.
+impl Uneval for String {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        self.as_str().uneval_ref(e)
+    }
+
+    fn const_type() -> Result<String> {
+        Ok("&'static str".to_string())
+    }
+}
+
+pub(crate) fn uneval_map<'a, K, V>(
+    entries: impl Iterator<Item = (&'a K, &'a V)>,
+    e: &mut Emitter<'_>,
+) -> Result<()>
+where
+    K: Uneval + 'a,
+    V: Uneval + 'a,
+{
+    e.write_str("&")?;
+    let mut array = e.array_expr();
+    for (key, value) in entries {
+        array.entry(&UnevalFn(|e: &mut Emitter<'_>| {
+            e.tuple_expr("").field(key).field(value).finish()
+        }));
+    }
+    array.finish()
+}
+
+/// Emitted as a `&'static [(K, V)]` slice sorted by key, so the output doesn't depend on
+/// the hasher and is the same at every run.
+impl<K: Uneval + Ord, V: Uneval, S> Uneval for HashMap<K, V, S> {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        uneval_map(entries.into_iter(), e)
+    }
+
+    fn const_type() -> Result<String> {
+        Ok(format!(
+            "&'static [({}, {})]",
+            K::const_type()?,
+            V::const_type()?
+        ))
+    }
+}
+
+/// Emitted as a `&'static [(K, V)]` slice sorted by key.
+impl<K: Uneval, V: Uneval> Uneval for BTreeMap<K, V> {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        uneval_map(self.iter(), e)
+    }
+
+    fn const_type() -> Result<String> {
+        Ok(format!(
+            "&'static [({}, {})]",
+            K::const_type()?,
+            V::const_type()?
+        ))
+    }
+}
+
+/// Emitted as a sorted `&'static [T]` slice, so the output doesn't depend on the hasher
+/// and is the same at every run.
+impl<T: Uneval + Ord, S> Uneval for HashSet<T, S> {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        let mut values: Vec<_> = self.iter().collect();
+        values.sort_unstable();
+        e.write_str("&")?;
+        e.array_expr().entries(values).finish()
+    }
+
+    fn const_type() -> Result<String> {
+        Ok(format!("&'static [{}]", T::const_type()?))
+    }
+}
+
+/// Emitted as a sorted `&'static [T]` slice.
+impl<T: Uneval> Uneval for BTreeSet<T> {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        e.write_str("&")?;
+        e.array_expr().entries(self).finish()
+    }
+
+    fn const_type() -> Result<String> {
+        Ok(format!("&'static [{}]", T::const_type()?))
+    }
+}
+
+/// Wrapper emitting `Vec` and `String` values as themselves instead of slices and
+/// `&str`.
+///
+/// Only empty values can be created in const context, as `Vec::new()` and
+/// `String::new()`, others fail with [Error::Unsupported][crate::Error::Unsupported]. It
+/// is meant for fields of a type shared with the generated code which are always empty in
+/// the generated values, e.g. caches. With the `derive` feature, `#[uneval(owned)]` wraps
+/// a field in `Owned`.
+///
+/// ```
+/// use constuneval::{to_string, Owned};
+///
+/// assert_eq!(
+///     to_string("EMPTY", &Owned(Vec::<u8>::new()), None),
+///     "const EMPTY: Vec<u8> = Vec::new();"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Owned<T>(pub T);
+
+fn uneval_owned(is_empty: bool, ty: &str, e: &mut Emitter<'_>) -> Result<()> {
+    if !is_empty {
+        return Err(e.error(format_args!(
+            "non empty `{}` can't be created in const context",
+            ty
+        )));
+    }
+    Ok(write!(e, "{}::new()", ty)?)
+}
+
+impl<T: Uneval> Uneval for Owned<Vec<T>> {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        uneval_owned(self.0.is_empty(), "Vec", e)
+    }
+
+    fn const_type() -> Result<String> {
+        Ok(format!("Vec<{}>", T::const_type()?))
+    }
+}
+
+impl<T: Uneval> Uneval for Owned<&Vec<T>> {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        uneval_owned(self.0.is_empty(), "Vec", e)
+    }
+
+    fn const_type() -> Result<String> {
+        Ok(format!("Vec<{}>", T::const_type()?))
+    }
+}
+
+impl Uneval for Owned<String> {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        uneval_owned(self.0.is_empty(), "String", e)
+    }
+
+    fn const_type() -> Result<String> {
+        Ok("String".to_string())
+    }
+}
+
+impl Uneval for Owned<&String> {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        uneval_owned(self.0.is_empty(), "String", e)
+    }
+
+    fn const_type() -> Result<String> {
+        Ok("String".to_string())
+    }
+}
+
+/// Fallback wrapper emitting the inner value with its `Debug` implementation.
+///
+/// This is how `constuneval` used to work before [Uneval]: the output of `{:#?}` is
+/// written verbatim, so the generated code is only valid if `Debug` happens to print
+/// valid Rust.
+///
+/// ```
+/// use constuneval::{to_string, ViaDebug};
+///
+/// #[derive(Debug)]
+/// struct Unit;
+///
+/// assert_eq!(
+///     to_string("UNIT", &ViaDebug(Unit), Some("Unit")),
+///     "const UNIT: Unit = Unit;"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct ViaDebug<T>(pub T);
+
+impl<T: fmt::Debug> Uneval for ViaDebug<T> {
+    /// Fails when the output contains `..`, as printed by `Debug` for types with
+    /// private fields through `finish_non_exhaustive()`, and warns that the output isn't
+    /// checked otherwise.
+    ///
+    /// The paths of constuneval items, `::constuneval::UnevalCow` written by the `Debug`
+    /// implementation of [UnevalCow], are replaced by the ones of
+    /// [Config::crate_path][crate::Config::crate_path] and
+    /// [Config::cow_path][crate::Config::cow_path].
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        let code = if e.is_compact() {
+            format!("{:?}", self.0)
+        } else {
+            format!("{:#?}", self.0)
+        };
+        if code.contains(" .. }") || code.lines().any(|line| line.trim() == "..") {
+            return Err(e.error("Debug output has non exhaustive fields"));
+        }
+        e.import_type::<T>();
+        e.warn(format_args!(
+            "`{}` is emitted with its Debug implementation, which may not produce valid Rust",
+            std::any::type_name::<T>()
+        ));
+        let code = crate::config::rewrite_crate_paths(&code, |name| e.crate_path(name));
+        Ok(e.write_str(&code)?)
+    }
+
+    fn const_type() -> Result<String> {
+        crate::ty::type_name::<T>()
+    }
+}
+
+/// Adapter for `#[uneval(with = "...")]`, not public API.
+#[doc(hidden)]
+pub struct UnevalFn<F>(pub F);
+
+impl<F> Uneval for UnevalFn<F>
+where
+    F: Fn(&mut Emitter<'_>) -> Result<()>,
+{
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        (self.0)(e)
+    }
+}
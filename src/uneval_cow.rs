@@ -1,6 +1,6 @@
 //! Fork of std::borrow::UnevalCow with more proper Debug trait.
 
-pub use core::borrow::{Borrow, BorrowMut};
+pub use core::borrow::Borrow;
 use core::cmp::Ordering;
 use core::hash::{Hash, Hasher};
 use core::iter::FromIterator;
@@ -22,7 +22,7 @@ where
     <B as ToOwned>::Owned: 'a,
 {
     fn borrow(&self) -> &B {
-        &**self
+        self
     }
 }
 
@@ -35,6 +35,11 @@ where
 ///   lifetime of `'static` if you plan to use `Debug` trait.
 ///
 /// ## Debug Trait Rules
+/// `UnevalCow` is always written as `::constuneval::UnevalCow`, whatever the formatter
+/// flags. [ViaDebug][crate::ViaDebug] replaces it by the path of
+/// [Config::cow_path][crate::Config::cow_path], importing it in a
+/// [module][crate::Config::module] as the [Uneval][crate::Uneval] implementation does.
+///
 /// - `UnevalCow::Borrowed(T)`          ==>  `UnevalCow::Borrowed(&T)`
 /// - `UnevalCow::Borrowed(&[T])`       ==>  `UnevalCow::Borrowed(&[T])`
 /// - `UnevalCow::Borrowed(Box<[T]>)`   ==>  `UnevalCow::Borrowed(&[T])`
@@ -121,7 +126,6 @@ where
 ///     _ => panic!("expect owned data"),
 /// }
 /// ```
-
 pub enum UnevalCow<'a, B: ?Sized + 'a>
 where
     B: ToOwned,
@@ -261,15 +265,16 @@ where
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use core::any::TypeId;
         let ty_id = TypeId::of::<B>();
-        let res;
 
+        f.write_str("::constuneval::UnevalCow")?;
         if ty_id == TypeId::of::<str>() {
-            res = f.write_fmt(format_args!("UnevalCow::Borrowed( {:?} )", &**self));
+            f.write_str("::Borrowed( ")?;
         } else {
-            res = f.write_fmt(format_args!("UnevalCow::Borrowed( &{:?} )", &**self));
+            f.write_str("::Borrowed( &")?;
         }
-
-        return res;
+        // through `f`, so that `{:#?}`, width and precision apply to the value
+        fmt::Debug::fmt(&**self, f)?;
+        f.write_str(" )")
         // match *self {
         //     Borrowed(ref b) => f.write_fmt(format_args!("UnevalCow::Borrowed( &{:?} )", b)),
         //     Owned(ref o) => {
@@ -0,0 +1,86 @@
+//! Validation of the generated code with `syn`.
+
+use std::error::Error;
+use std::fmt;
+
+/// Error returned when the generated code doesn't parse as Rust items.
+///
+/// Only available with the `validate` feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    message: String,
+    line: usize,
+    column: usize,
+    snippet: String,
+}
+
+impl ValidationError {
+    /// Message of the parser.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Line of the error in the generated code, starting from 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Column of the error in the generated code, starting from 1.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Line of generated code containing the error.
+    pub fn snippet(&self) -> &str {
+        &self.snippet
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "generated code is not valid Rust: {} (line {}, column {})",
+            self.message, self.line, self.column
+        )?;
+        writeln!(f, "{}", self.snippet)?;
+        write!(f, "{:>width$}", "^", width = self.column)
+    }
+}
+
+impl Error for ValidationError {}
+
+/// Check that `code` parses as a sequence of Rust items.
+///
+/// With the `validate` feature, this is done by every function generating code, so
+/// invalid code (e.g. from a [ViaDebug][crate::ViaDebug] value) makes the build script
+/// fail with a clear error instead of the crate including it.
+///
+/// ```
+/// assert!(constuneval::validate("const A: u8 = 1;").is_ok());
+///
+/// let err = constuneval::validate("const A: f64 = NaN;\nconst B: () = {a: 1};").unwrap_err();
+/// assert_eq!((err.line(), err.column()), (2, 17));
+/// assert_eq!(err.snippet(), "const B: () = {a: 1};");
+/// ```
+pub fn validate(code: &str) -> Result<(), ValidationError> {
+    let err = match syn::parse_file(code) {
+        Ok(_) => return Ok(()),
+        Err(err) => err,
+    };
+    let start = err.span().start();
+    // errors at the end of input have an empty call site span
+    let (line, column) = if err.span().byte_range().is_empty() {
+        let last = code.lines().count().max(1);
+        let len = code.lines().last().map_or(0, |l| l.chars().count());
+        (last, len + 1)
+    } else {
+        (start.line, start.column + 1)
+    };
+    Err(ValidationError {
+        message: err.to_string(),
+        line,
+        column,
+        snippet: code.lines().nth(line - 1).unwrap_or("").to_string(),
+    })
+}
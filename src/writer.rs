@@ -0,0 +1,329 @@
+//! Builder generating a file with multiple items.
+
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::config::{import_name, write_module};
+use crate::file::{write_file_with, IoWriter, Sidecars};
+use crate::{Config, Error, Header, Result, Uneval, WriteMode};
+
+/// Writes an item, returns the paths to import when it's in a module.
+type EmitFn<'a> =
+    Box<dyn Fn(&mut dyn fmt::Write, Option<&mut Sidecars>, bool) -> Result<BTreeSet<String>> + 'a>;
+
+enum Input {
+    File(PathBuf),
+    Env(String),
+}
+
+enum Part<'a> {
+    Use(String),
+    TypeAlias {
+        name: String,
+        ty: String,
+    },
+    Raw(String),
+    Item {
+        name: String,
+        /// File option set by the configuration of the item.
+        file_option: Option<&'static str>,
+        emit: EmitFn<'a>,
+    },
+}
+
+/// Builder accumulating many items into a single generated file.
+///
+/// Parts are written in the order they were added. Nothing is written until
+/// [write()][ConstWriter::write] is called, which first checks that no item or type
+/// alias name is used twice.
+///
+/// ```no_run
+/// use constuneval::{Config, ConstWriter, ItemKind};
+///
+/// let squares: Vec<u32> = (0..16).map(|i| i * i).collect();
+/// ConstWriter::new("tables.rs")
+///     .input_env("TABLE_SIZE")
+///     .use_item("std::num::Wrapping")
+///     .type_alias("Table", "&'static [u32]")
+///     .item("SQUARES", &squares.as_slice(), Some("Table"))
+///     .item_with(Config::new().kind(ItemKind::Static), "ZERO", &0_u32, Some("u32"))
+///     .raw("const ONE: Wrapping<u32> = Wrapping(1);")
+///     .write()
+///     .expect("Write failed");
+/// ```
+pub struct ConstWriter<'a> {
+    target: PathBuf,
+    config: Config,
+    write_mode: WriteMode,
+    header: Header,
+    module: Option<String>,
+    inputs: Vec<Input>,
+    parts: Vec<Part<'a>>,
+}
+
+impl<'a> ConstWriter<'a> {
+    /// Create a writer for the file at `target`.
+    pub fn new(target: impl AsRef<Path>) -> Self {
+        ConstWriter {
+            target: target.as_ref().to_path_buf(),
+            config: Config::default(),
+            write_mode: WriteMode::default(),
+            header: Header::default(),
+            module: None,
+            inputs: Vec::new(),
+            parts: Vec::new(),
+        }
+    }
+
+    /// Set the configuration used by [item()][ConstWriter::item], without the options of
+    /// the file, see [item_with()][ConstWriter::item_with].
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Set how [write()][ConstWriter::write] writes the file, see [WriteMode].
+    pub fn write_mode(mut self, mode: WriteMode) -> Self {
+        self.write_mode = mode;
+        self
+    }
+
+    /// Set the header of the file, see [Header].
+    pub fn header(mut self, header: Header) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Wrap the content of the file in `pub mod name { ... }`, with `use` statements
+    /// importing the types the items refer to by name, see [Config::module].
+    ///
+    /// Names already imported with [use_item()][ConstWriter::use_item] are not imported
+    /// again. The content of the module isn't indented, so that raw code is written
+    /// unchanged.
+    ///
+    /// ```
+    /// use constuneval::{Config, ConstWriter, Header, UnevalCow};
+    ///
+    /// let digits: UnevalCow<[u8]> = UnevalCow::Owned(vec![1, 2]);
+    /// let code = ConstWriter::new("unused.rs")
+    ///     .config(Config::new().cow_path("UnevalCow"))
+    ///     .header(Header::none())
+    ///     .module("tables")
+    ///     .item("DIGITS", &digits, None)
+    ///     .render()?;
+    /// assert_eq!(
+    ///     code,
+    ///     "pub mod tables {
+    /// pub(super) const DIGITS: UnevalCow<'static, [u8]> = UnevalCow::Borrowed(
+    ///     &[
+    ///         1,
+    ///         2,
+    ///     ],
+    /// );
+    /// use super::UnevalCow;
+    /// }
+    /// "
+    /// );
+    /// # Ok::<(), constuneval::Error>(())
+    /// ```
+    pub fn module(mut self, name: &str) -> Self {
+        self.module = Some(name.to_string());
+        self
+    }
+
+    /// Register a file or directory the generated code depends on.
+    ///
+    /// [write()][ConstWriter::write] tells Cargo to rerun the build script when it
+    /// changes, see [rerun_if_changed()][crate::rerun_if_changed].
+    pub fn input_file(mut self, path: impl AsRef<Path>) -> Self {
+        self.inputs.push(Input::File(path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Register an environment variable the generated code depends on.
+    ///
+    /// [write()][ConstWriter::write] tells Cargo to rerun the build script when it
+    /// changes, see [rerun_if_env_changed()][crate::rerun_if_env_changed].
+    pub fn input_env(mut self, var: &str) -> Self {
+        self.inputs.push(Input::Env(var.to_string()));
+        self
+    }
+
+    /// Add an item generated with the writer's configuration, see
+    /// [to_string()][crate::to_string] for the arguments.
+    pub fn item<T: Uneval + ?Sized>(self, name: &str, value: &'a T, ty: Option<&str>) -> Self {
+        let config = self.config.clone();
+        self.item_with(config, name, value, ty)
+    }
+
+    /// Add an item generated with its own configuration.
+    ///
+    /// The options of the file, [module()][Config::module], [header()][Config::header]
+    /// and [write_mode()][Config::write_mode], are set on the writer: setting them in
+    /// `config` is an [Error::FileOption].
+    pub fn item_with<T: Uneval + ?Sized>(
+        mut self,
+        config: Config,
+        name: &str,
+        value: &'a T,
+        ty: Option<&str>,
+    ) -> Self {
+        let item_name = name.to_string();
+        let ty = ty.map(str::to_string);
+        self.parts.push(Part::Item {
+            name: name.to_string(),
+            file_option: config.file_option(),
+            emit: Box::new(move |out, sidecars, in_module| {
+                config.write_item(out, &item_name, value, ty.as_deref(), sidecars, in_module)
+            }),
+        });
+        self
+    }
+
+    /// Add a `use path;` statement.
+    pub fn use_item(mut self, path: &str) -> Self {
+        self.parts.push(Part::Use(path.to_string()));
+        self
+    }
+
+    /// Add a `type name = ty;` alias.
+    pub fn type_alias(mut self, name: &str, ty: &str) -> Self {
+        self.parts.push(Part::TypeAlias {
+            name: name.to_string(),
+            ty: ty.to_string(),
+        });
+        self
+    }
+
+    /// Add a snippet of Rust code, written as is.
+    pub fn raw(mut self, code: &str) -> Self {
+        self.parts.push(Part::Raw(code.to_string()));
+        self
+    }
+
+    /// Generate the content of the file, starting with its [Header].
+    ///
+    /// Fails with [Error::DuplicateNames] if an item or type alias name is used more than
+    /// once, with [Error::FileOption] if the configuration of an item sets an option of
+    /// the file, or if generating an item fails (see [Config::try_to_string]).
+    pub fn render(&self) -> Result<String> {
+        self.check_parts()?;
+        let mut out = String::new();
+        self.write_parts(&mut out, None)?;
+        let out = self.header.prepend(out);
+        #[cfg(feature = "validate")]
+        crate::validate(&out)?;
+        Ok(out)
+    }
+
+    fn write_parts(&self, out: &mut dyn fmt::Write, sidecars: Option<&mut Sidecars>) -> Result<()> {
+        match &self.module {
+            Some(module) => {
+                write_module(out, module, |out| {
+                    let mut imports = self.write_contents(out, sidecars, true)?;
+                    // a second import of the same name would conflict with the first one
+                    for part in &self.parts {
+                        if let Part::Use(path) = part {
+                            imports.retain(|import| import_name(import) != import_name(path));
+                        }
+                    }
+                    Ok(imports)
+                })?;
+                Ok(out.write_char('\n')?)
+            }
+            None => self.write_contents(out, sidecars, false).map(|_| ()),
+        }
+    }
+
+    /// Write the parts, returns the paths to import when they are `in_module`.
+    fn write_contents(
+        &self,
+        out: &mut dyn fmt::Write,
+        mut sidecars: Option<&mut Sidecars>,
+        in_module: bool,
+    ) -> Result<BTreeSet<String>> {
+        let mut imports = BTreeSet::new();
+        for part in &self.parts {
+            match part {
+                Part::Use(path) => writeln!(out, "use {};", path)?,
+                Part::TypeAlias { name, ty } => writeln!(out, "type {} = {};", name, ty)?,
+                Part::Raw(code) => writeln!(out, "{}", code.trim_end_matches('\n'))?,
+                Part::Item { emit, .. } => {
+                    imports.extend(emit(out, sidecars.as_deref_mut(), in_module)?);
+                    out.write_char('\n')?;
+                }
+            }
+        }
+        Ok(imports)
+    }
+
+    /// Generate the file, nothing is written if [render()][ConstWriter::render] fails.
+    ///
+    /// Items are streamed to the file as they are generated, except with the `validate`
+    /// feature which needs the whole file in memory.
+    ///
+    /// When called from a build script, `cargo:rerun-if-*` directives are printed for the
+    /// registered inputs, even if it fails.
+    pub fn write(&self) -> Result<()> {
+        for input in &self.inputs {
+            match input {
+                Input::File(path) => crate::rerun_if_changed(path),
+                Input::Env(var) => {
+                    crate::rerun_if_env_changed(var);
+                }
+            }
+        }
+        self.check_parts()?;
+        let mut sidecars = Sidecars::new(&self.target, self.write_mode);
+        write_file_with(&self.target, self.write_mode, |out| {
+            self.header.write(out, |out| {
+                if cfg!(feature = "validate") {
+                    let mut code = String::new();
+                    self.write_parts(&mut code, Some(&mut sidecars))?;
+                    #[cfg(feature = "validate")]
+                    crate::validate(&code)?;
+                    Ok(out.write_all(code.as_bytes())?)
+                } else {
+                    let mut out = IoWriter::new(out);
+                    let res = self.write_parts(&mut out, Some(&mut sidecars));
+                    out.finish(res)
+                }
+            })
+        })?;
+        Ok(sidecars.finish()?)
+    }
+
+    fn check_parts(&self) -> Result<()> {
+        let mut items = HashSet::new();
+        let mut aliases = HashSet::new();
+        let mut duplicates = Vec::new();
+        for part in &self.parts {
+            let (seen, name) = match part {
+                Part::Item {
+                    name,
+                    file_option: Some(option),
+                    ..
+                } => {
+                    return Err(Error::FileOption {
+                        item: name.clone(),
+                        option,
+                    })
+                }
+                // `const _` items are anonymous, there can be many of them
+                Part::Item { name, .. } if name == "_" => continue,
+                Part::Item { name, .. } => (&mut items, name),
+                Part::TypeAlias { name, .. } => (&mut aliases, name),
+                _ => continue,
+            };
+            if !seen.insert(name) {
+                duplicates.push(name.clone());
+            }
+        }
+        if duplicates.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::DuplicateNames(duplicates))
+        }
+    }
+}
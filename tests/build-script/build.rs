@@ -0,0 +1,20 @@
+use constuneval::{out_path, Config, ConstWriter, Phf, Visibility};
+use std::collections::HashMap;
+
+fn main() -> constuneval::Result<()> {
+    let squares: Vec<u32> = (0..8).map(|i| i * i).collect();
+    let digits: HashMap<_, _> = ["zero", "one", "two"]
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (name.to_string(), i as u8))
+        .collect();
+    ConstWriter::new(out_path("tables.rs")?)
+        .config(Config::new().vis(Visibility::Public))
+        .item("SQUARES", &squares, None)
+        .item("DIGITS", &Phf(digits), None)
+        .write()?;
+    Config::new()
+        .vis(Visibility::Public)
+        .module("version")
+        .to_out_dir("version.rs", "NAME", &"build-script", None)
+}
@@ -0,0 +1,7 @@
+//! Code generated by the build script, included with `include_generated!`.
+
+// the generated items spell out `'static`
+#![allow(clippy::redundant_static_lifetimes)]
+
+constuneval::include_generated!("tables.rs");
+constuneval::include_generated!("version.rs");
@@ -0,0 +1,9 @@
+use constuneval_build_script::{version, DIGITS, SQUARES};
+
+#[test]
+fn test_include_generated() {
+    assert_eq!(SQUARES, &[0, 1, 4, 9, 16, 25, 36, 49]);
+    assert_eq!(DIGITS.get("two"), Some(&2));
+    assert_eq!(DIGITS.get("three"), None);
+    assert_eq!(version::NAME, "build-script");
+}
@@ -0,0 +1,167 @@
+extern crate constuneval;
+
+use constuneval::{
+    is_build_script, out_path, rerun_if_changed, rerun_if_env_changed, to_out_dir, warning,
+    ConstWriter, Emitter, Error, Header, Result, Uneval,
+};
+use std::ffi::OsStr;
+use std::path::Path;
+use std::process::Command;
+use std::{env, fs};
+
+/// Set for the test binary run by `run_child`.
+const CHILD: &str = "CONSTUNEVAL_TEST_CHILD";
+
+/// Run `test` in a child process with the environment variables of a build script
+/// cleared, then set to `vars`, returns its stdout.
+///
+/// This way, no test modifies the environment of the others, and directives printed to
+/// stdout can be checked.
+fn run_child(test: &str, vars: &[(&str, &OsStr)]) -> String {
+    let mut command = Command::new(env::current_exe().unwrap());
+    command
+        .args([test, "--exact", "--nocapture"])
+        .env(CHILD, "1");
+    for var in ["OUT_DIR", "TARGET", "HOST", "NUM_JOBS"] {
+        command.env_remove(var);
+    }
+    let output = command.envs(vars.iter().copied()).output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}{}", stdout, stderr);
+    assert!(stdout.contains("1 passed"), "{}", stdout);
+    stdout
+}
+
+/// Variables set by cargo when running the build script, with `dir` as `OUT_DIR`.
+fn build_script_vars(dir: &Path) -> [(&str, &OsStr); 4] {
+    let target = OsStr::new("x86_64-unknown-linux-gnu");
+    [
+        ("OUT_DIR", dir.as_os_str()),
+        ("TARGET", target),
+        ("HOST", target),
+        ("NUM_JOBS", OsStr::new("1")),
+    ]
+}
+
+#[test]
+fn test_not_build_script() {
+    if env::var_os(CHILD).is_none() {
+        run_child("test_not_build_script", &[]);
+        // as when running the tests of a package with a build script
+        let dir = env::temp_dir();
+        run_child("test_not_build_script", &[("OUT_DIR", dir.as_os_str())]);
+        return;
+    }
+    assert!(!is_build_script());
+    assert!(matches!(out_path("a.rs"), Err(Error::NotBuildScript)));
+    let err = to_out_dir("a.rs", "A", &1_u8, Some("u8")).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "constuneval must be called from a build script, \
+         `OUT_DIR`, `TARGET`, `HOST` or `NUM_JOBS` is not set"
+    );
+}
+
+#[test]
+fn test_out_dir() {
+    if env::var_os(CHILD).is_some() {
+        assert!(is_build_script());
+        assert_eq!(
+            rerun_if_env_changed("CONSTUNEVAL_TEST_OUT_DIR").as_deref(),
+            Some("1")
+        );
+        let dir = env::var_os("OUT_DIR").unwrap();
+        assert_eq!(out_path("a.rs").unwrap(), Path::new(&dir).join("a.rs"));
+        to_out_dir("a.rs", "A", &1_u8, Some("u8")).unwrap();
+        return;
+    }
+    let dir = env::temp_dir().join("constuneval_test_out_dir");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir(&dir).unwrap();
+    let mut vars = build_script_vars(&dir).to_vec();
+    vars.push(("CONSTUNEVAL_TEST_OUT_DIR", OsStr::new("1")));
+    run_child("test_out_dir", &vars);
+    let code = fs::read_to_string(dir.join("a.rs")).unwrap();
+    assert!(code.starts_with(concat!(
+        "// @generated by constuneval ",
+        env!("CARGO_PKG_VERSION"),
+        " from the build script of constuneval ",
+        env!("CARGO_PKG_VERSION"),
+        ", do not edit.\n// content-hash: fnv1a64:"
+    )));
+    assert!(code.ends_with("\n\nconst A: u8 = 1;\n"));
+    assert!(Header::verify(&code));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_directives() {
+    if env::var_os(CHILD).is_some() {
+        print_directives();
+        return;
+    }
+    let dir = env::temp_dir().join("constuneval_test_directives");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir(&dir).unwrap();
+    let mut vars = build_script_vars(&dir).to_vec();
+    vars.push(("CONSTUNEVAL_TEST_DIRECTIVES_VAR", OsStr::new("1")));
+    let stdout = run_child("test_directives", &vars);
+    // the first one follows the name of the test printed by libtest
+    let directives: Vec<_> = stdout
+        .lines()
+        .filter_map(|line| line.find("cargo:").map(|start| &line[start..]))
+        .collect();
+    assert_eq!(
+        directives,
+        [
+            "cargo:rerun-if-changed=data/table.csv",
+            "cargo:rerun-if-env-changed=CONSTUNEVAL_TEST_DIRECTIVES_VAR",
+            "cargo:warning=first",
+            "cargo:warning=second",
+            "cargo:rerun-if-changed=data/table.csv",
+            "cargo:rerun-if-env-changed=TABLE_SIZE",
+            "cargo:warning=LOSSY[0]: rounded to an integer (and 1 more)",
+        ]
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+fn print_directives() {
+    rerun_if_changed("data/table.csv");
+    assert_eq!(
+        rerun_if_env_changed("CONSTUNEVAL_TEST_DIRECTIVES_VAR").as_deref(),
+        Some("1")
+    );
+    warning("first\nsecond");
+
+    // warnings are deduplicated, and reported even if the item fails
+    let lossy = [Lossy(1.5), Lossy(2.0), Lossy(2.5), Lossy(-1.0)];
+    let res = ConstWriter::new(out_path("b.rs").unwrap())
+        .input_file("data/table.csv")
+        .input_env("TABLE_SIZE")
+        .item("LOSSY", &lossy, None)
+        .write();
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "LOSSY[3]: negative values are unsupported"
+    );
+}
+
+struct Lossy(f32);
+
+impl Uneval for Lossy {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        if self.0 < 0.0 {
+            return Err(e.error("negative values are unsupported"));
+        }
+        if self.0.fract() != 0.0 {
+            e.warn("rounded to an integer");
+        }
+        (self.0.round() as u32).uneval(e)
+    }
+
+    fn const_type() -> Result<String> {
+        Ok("u32".to_string())
+    }
+}
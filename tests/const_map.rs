@@ -0,0 +1,52 @@
+extern crate constuneval;
+
+use constuneval::{try_to_string, ConstMap};
+
+const PRIMES: ConstMap<'static, u32, &'static str> =
+    ConstMap::new(&[(2, "two"), (3, "three"), (5, "five"), (7, "seven")]);
+
+#[test]
+fn test_const_map_lookup() {
+    assert_eq!(PRIMES.len(), 4);
+    assert_eq!(PRIMES.get(&5), Some(&"five"));
+    assert_eq!(PRIMES.get(&4), None);
+    assert!(PRIMES.contains_key(&2));
+    assert_eq!(PRIMES.keys().copied().collect::<Vec<_>>(), [2, 3, 5, 7]);
+    assert_eq!(
+        PRIMES.values().copied().collect::<Vec<_>>(),
+        ["two", "three", "five", "seven"]
+    );
+    let keys =
+        |range: std::slice::Iter<'static, (u32, &str)>| range.map(|(k, _)| *k).collect::<Vec<_>>();
+    assert_eq!(keys(PRIMES.range(3..7)), [3, 5]);
+    assert_eq!(keys(PRIMES.range(3..=7)), [3, 5, 7]);
+    assert_eq!(keys(PRIMES.range(..4)), [2, 3]);
+    assert_eq!(keys(PRIMES.range(6..)), [7]);
+    assert_eq!(keys(PRIMES.range(4..5)), [0_u32; 0]);
+
+    let names = vec![("a".to_string(), 1), ("b".to_string(), 2)];
+    let map = ConstMap::new(&names);
+    assert_eq!(map.get("b"), Some(&2));
+}
+
+#[test]
+fn test_const_map_uneval() {
+    let entries = vec![("a".to_string(), 1_u8)];
+    assert_eq!(
+        try_to_string("MAP", &ConstMap::new(&entries), None).unwrap(),
+        "const MAP: ::constuneval::ConstMap<'static, &'static str, u8> = \
+         ::constuneval::ConstMap::new(
+    &[
+        (
+            \"a\",
+            1,
+        ),
+    ],
+);"
+    );
+
+    let err = try_to_string("MAP", &ConstMap::new(&[(1, ()), (1, ())]), None).unwrap_err();
+    assert_eq!(err.to_string(), "MAP: keys 0 and 1 are equal");
+    let err = try_to_string("MAP", &ConstMap::new(&[(1, ()), (3, ()), (2, ())]), None).unwrap_err();
+    assert_eq!(err.to_string(), "MAP: keys 1 and 2 are not sorted");
+}
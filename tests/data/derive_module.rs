@@ -0,0 +1,15 @@
+pub mod domain {
+pub(super) const D: FftDomain<u8> = FftDomain { some_table: ::constuneval::UnevalCow::Borrowed(&[::constuneval::UnevalCow::Borrowed(&[1])]) };
+use ::derive::FftDomain;
+}
+pub mod entry {
+pub(super) const E: tables::Entry = tables::Entry { key: "a", mask: 0x1 };
+use super::tables;
+}
+pub mod empty {
+pub(super) const NONE: &'static [FftDomain<u8>] = &[];
+use ::derive::FftDomain;
+}
+pub mod full_path {
+pub(super) const NONE: &'static [::derive::FftDomain<u8>] = &[];
+}
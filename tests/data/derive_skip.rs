@@ -0,0 +1,12 @@
+const ENTRY: tables::Entry = tables::Entry {
+    key: "a",
+    mask: 0xff,
+};
+const CACHED: Cached = Cached {
+    name: "b",
+    cache: Vec::new(),
+};
+const COUNTER: Counter = Counter(
+    1,
+    0,
+);
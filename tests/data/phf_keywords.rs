@@ -0,0 +1,175 @@
+const KEYWORD_MAP: ::constuneval::PhfMap<&'static str, usize> = ::constuneval::PhfMap::new(
+    15839785061582574730,
+    &[
+        (
+            0,
+            3,
+        ),
+        (
+            0,
+            0,
+        ),
+        (
+            9,
+            4,
+        ),
+        (
+            24,
+            0,
+        ),
+        (
+            0,
+            11,
+        ),
+        (
+            2,
+            15,
+        ),
+        (
+            27,
+            1,
+        ),
+    ],
+    &[
+        (
+            "return",
+            22,
+        ),
+        (
+            "struct",
+            26,
+        ),
+        (
+            "const",
+            2,
+        ),
+        (
+            "mut",
+            19,
+        ),
+        (
+            "in",
+            13,
+        ),
+        (
+            "static",
+            25,
+        ),
+        (
+            "break",
+            1,
+        ),
+        (
+            "for",
+            10,
+        ),
+        (
+            "loop",
+            15,
+        ),
+        (
+            "type",
+            30,
+        ),
+        (
+            "where",
+            33,
+        ),
+        (
+            "enum",
+            6,
+        ),
+        (
+            "Self",
+            24,
+        ),
+        (
+            "crate",
+            4,
+        ),
+        (
+            "impl",
+            12,
+        ),
+        (
+            "ref",
+            21,
+        ),
+        (
+            "super",
+            27,
+        ),
+        (
+            "trait",
+            28,
+        ),
+        (
+            "move",
+            18,
+        ),
+        (
+            "continue",
+            3,
+        ),
+        (
+            "if",
+            11,
+        ),
+        (
+            "use",
+            32,
+        ),
+        (
+            "while",
+            34,
+        ),
+        (
+            "true",
+            29,
+        ),
+        (
+            "pub",
+            20,
+        ),
+        (
+            "else",
+            5,
+        ),
+        (
+            "fn",
+            9,
+        ),
+        (
+            "match",
+            16,
+        ),
+        (
+            "extern",
+            7,
+        ),
+        (
+            "as",
+            0,
+        ),
+        (
+            "unsafe",
+            31,
+        ),
+        (
+            "let",
+            14,
+        ),
+        (
+            "mod",
+            17,
+        ),
+        (
+            "false",
+            8,
+        ),
+        (
+            "self",
+            23,
+        ),
+    ],
+);
@@ -0,0 +1,252 @@
+// the generated items spell out `'static`
+#![allow(clippy::redundant_static_lifetimes)]
+
+extern crate constuneval;
+
+use constuneval::{to_string, Emitter, Result, UnevalCow};
+use constuneval_derive::Uneval;
+use std::fmt::Write;
+
+#[derive(Uneval)]
+pub struct FftDomain<F>
+where
+    [F]: 'static + ToOwned,
+{
+    pub some_table: UnevalCow<'static, [UnevalCow<'static, [F]>]>,
+}
+
+#[derive(Uneval)]
+struct Unit;
+
+#[derive(Uneval)]
+struct Pair(u8, &'static str);
+
+#[derive(Uneval)]
+#[allow(dead_code)]
+enum Shape {
+    Empty,
+    Circle(u32),
+    Rect { w: u32, h: u32 },
+}
+
+#[derive(Uneval)]
+enum Never {}
+
+fn hex(value: &u32, e: &mut Emitter<'_>) -> Result<()> {
+    Ok(write!(e, "{:#x}", value)?)
+}
+
+#[derive(Uneval)]
+#[uneval(path = "tables::Entry")]
+struct Entry {
+    #[uneval(rename = "key")]
+    name: &'static str,
+    #[uneval(with = "hex")]
+    mask: u32,
+    #[uneval(skip)]
+    #[allow(dead_code)]
+    cache: Vec<u8>,
+}
+
+#[derive(Uneval)]
+struct Cached {
+    name: &'static str,
+    #[uneval(skip = "Vec::new()")]
+    cache: Vec<u8>,
+}
+
+#[derive(Uneval)]
+struct Counter(u8, #[uneval(skip = "0")] u16);
+
+/// Type emitted for `Entry`, without its skipped field.
+mod tables {
+    pub struct Entry {
+        pub key: &'static str,
+        pub mask: u32,
+    }
+
+    #[allow(dead_code)]
+    pub struct Record {
+        pub name: &'static str,
+        pub values: &'static [u16],
+        pub cache: Vec<u16>,
+    }
+}
+
+include!("data/derive_skip.rs");
+
+// `::derive` paths, which module mode uses for the types of this crate, resolve in it too
+extern crate self as derive;
+
+include!("data/derive_module.rs");
+
+#[derive(Uneval)]
+#[uneval(path = "tables::Record")]
+struct Record {
+    name: String,
+    values: Vec<u16>,
+    #[uneval(owned)]
+    cache: Vec<u16>,
+}
+
+#[test]
+fn test_derive_struct() {
+    let fft = FftDomain {
+        some_table: UnevalCow::Owned(vec![UnevalCow::Owned(vec![1, 2])]),
+    };
+    assert_eq!(
+        to_string("FFT", &fft, Some("FftDomain<'static, i32>")),
+        "const FFT: FftDomain<'static, i32> = FftDomain {
+    some_table: ::constuneval::UnevalCow::Borrowed(
+        &[
+            ::constuneval::UnevalCow::Borrowed(
+                &[
+                    1,
+                    2,
+                ],
+            ),
+        ],
+    ),
+};"
+    );
+    assert_eq!(to_string("U", &Unit, Some("Unit")), "const U: Unit = Unit;");
+    assert_eq!(
+        to_string("P", &Pair(1, "a"), Some("Pair")),
+        "const P: Pair = Pair(\n    1,\n    \"a\",\n);"
+    );
+}
+
+#[test]
+fn test_derive_const_type() {
+    use constuneval::Uneval;
+
+    assert_eq!(FftDomain::<i32>::const_type().unwrap(), "FftDomain<i32>");
+    assert_eq!(Shape::const_type().unwrap(), "Shape");
+    assert_eq!(Entry::const_type().unwrap(), "tables::Entry");
+
+    #[derive(Uneval)]
+    struct Wrapper<'a, T, const N: usize>(&'a [T; N]);
+    assert_eq!(
+        Wrapper::<u8, 2>::const_type().unwrap(),
+        "Wrapper<'static, u8, 2>"
+    );
+}
+
+#[test]
+fn test_derive_owned() {
+    let record = Record {
+        name: "a".to_string(),
+        values: vec![1],
+        cache: Vec::new(),
+    };
+    // the expected output, which compiles with the slice and `&str` fields of the path
+    const _: tables::Record = tables::Record {
+        name: "a",
+        values: &[1],
+        cache: Vec::new(),
+    };
+    assert_eq!(
+        to_string("R", &record, None),
+        "const R: tables::Record = tables::Record {
+    name: \"a\",
+    values: &[
+        1,
+    ],
+    cache: Vec::new(),
+};"
+    );
+}
+
+#[test]
+fn test_derive_enum() {
+    assert_eq!(
+        to_string("S", &Shape::Empty, Some("Shape")),
+        "const S: Shape = Shape::Empty;"
+    );
+    assert_eq!(
+        to_string("S", &Shape::Circle(3), Some("Shape")),
+        "const S: Shape = Shape::Circle(\n    3,\n);"
+    );
+    assert_eq!(
+        to_string("S", &Shape::Rect { w: 1, h: 2 }, Some("Shape")),
+        "const S: Shape = Shape::Rect {\n    w: 1,\n    h: 2,\n};"
+    );
+    let _ = |never: &Never| to_string("N", never, None);
+}
+
+#[test]
+fn test_derive_attributes() {
+    let entry = Entry {
+        name: "a",
+        mask: 255,
+        cache: vec![1],
+    };
+    assert_eq!(
+        to_string("E", &entry, Some("tables::Entry")),
+        "const E: tables::Entry = tables::Entry {\n    key: \"a\",\n    mask: 0xff,\n};"
+    );
+}
+
+#[test]
+fn test_derive_skip() {
+    // the expected output is also included above, which checks that it compiles
+    let entry = Entry {
+        name: "a",
+        mask: 255,
+        cache: vec![1],
+    };
+    let cached = Cached {
+        name: "b",
+        cache: vec![2],
+    };
+    let code = [
+        to_string("ENTRY", &entry, None),
+        to_string("CACHED", &cached, None),
+        to_string("COUNTER", &Counter(1, 2), None),
+    ];
+    assert_eq!(code.join("\n") + "\n", include_str!("data/derive_skip.rs"));
+    assert_eq!((ENTRY.key, ENTRY.mask), ("a", 255));
+    assert_eq!(CACHED.name, "b");
+    assert!(CACHED.cache.is_empty());
+    assert_eq!((COUNTER.0, COUNTER.1), (1, 0));
+}
+
+#[test]
+fn test_derive_module() {
+    use constuneval::{Config, Style};
+
+    // the expected output is also included above, which checks that it compiles
+    let config = |module: &str| Config::new().style(Style::Compact).module(module);
+    let domain = FftDomain {
+        some_table: UnevalCow::Owned(vec![UnevalCow::Borrowed(&[1_u8][..])]),
+    };
+    let entry = Entry {
+        name: "a",
+        mask: 1,
+        cache: Vec::new(),
+    };
+    let empty: Vec<FftDomain<u8>> = Vec::new();
+    let code = [
+        config("domain").to_string("D", &domain, None),
+        // the first segment of relative paths is imported from the including scope
+        config("entry").to_string("E", &entry, None),
+        // the types only appearing in the item type are imported too
+        config("empty").to_string("NONE", &empty, None),
+        config("full_path").to_string("NONE", &empty, Some("&'static [::derive::FftDomain<u8>]")),
+    ];
+    assert_eq!(
+        code.join("\n") + "\n",
+        include_str!("data/derive_module.rs")
+    );
+    assert_eq!(domain::D.some_table.len(), 1);
+    assert_eq!((entry::E.key, entry::E.mask), ("a", 1));
+    assert!(empty::NONE.is_empty() && full_path::NONE.is_empty());
+}
+
+#[test]
+fn test_derive_compile_fail() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/derive_vec_field.rs");
+    cases.compile_fail("tests/ui/derive_nested_vec_field.rs");
+    cases.compile_fail("tests/ui/derive_map_field.rs");
+}
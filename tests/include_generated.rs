@@ -0,0 +1,9 @@
+extern crate constuneval;
+
+// The files generated by a build script are included by the `build-script` test crate,
+// this checks the message given without one.
+#[test]
+fn test_include_generated_without_out_dir() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/include_generated_without_out_dir.rs");
+}
@@ -0,0 +1,91 @@
+extern crate constuneval;
+
+use constuneval::{to_string, Phf, PhfMap};
+use std::collections::HashMap;
+
+const KEYWORDS: &[&str] = &[
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where",
+    "while",
+];
+
+include!("data/phf_keywords.rs");
+
+fn keywords() -> HashMap<String, usize> {
+    KEYWORDS
+        .iter()
+        .enumerate()
+        .map(|(i, k)| (k.to_string(), i))
+        .collect()
+}
+
+#[test]
+fn test_phf_generate() {
+    // Also checks the output doesn't depend on the iteration order of the map.
+    assert_eq!(
+        to_string("KEYWORD_MAP", &Phf(keywords()), None) + "\n",
+        include_str!("data/phf_keywords.rs")
+    );
+    assert_eq!(
+        to_string("EMPTY", &Phf(HashMap::<u32, ()>::new()), None),
+        "const EMPTY: ::constuneval::PhfMap<u32, ()> = ::constuneval::PhfMap::new(\n    0,\n    \
+         &[],\n    &[],\n);"
+    );
+}
+
+#[test]
+fn test_phf_get() {
+    assert_eq!(KEYWORD_MAP.len(), KEYWORDS.len());
+    for (i, keyword) in KEYWORDS.iter().enumerate() {
+        assert_eq!(KEYWORD_MAP.get(*keyword), Some(&i));
+        assert!(KEYWORD_MAP.contains_key(keyword));
+    }
+    for other in &["", "a", "Fn", "matches", "whil"] {
+        assert_eq!(KEYWORD_MAP.get(*other), None);
+    }
+
+    const EMPTY: PhfMap<u32, ()> = PhfMap::new(0, &[], &[]);
+    assert!(EMPTY.is_empty());
+    assert!(!EMPTY.contains_key(&0));
+}
+
+#[test]
+fn test_phf_large() {
+    use constuneval::{Config, Style};
+
+    fn numbers(code: &str) -> Vec<u64> {
+        code.split(|c: char| !c.is_ascii_digit())
+            .filter(|number| !number.is_empty())
+            .map(|number| number.parse().unwrap())
+            .collect()
+    }
+
+    let map: HashMap<usize, u32> = (0..10_000_u32)
+        .map(|i| ((i as usize).wrapping_mul(0x9e37_79b9), i))
+        .collect();
+    let code = Config::new()
+        .style(Style::Compact)
+        .to_string("MAP", &Phf(&map), None);
+    let args = code.split("::new(").nth(1).unwrap();
+    let (key, rest) = args.split_once(", &[").unwrap();
+    let (disps, entries) = rest.split_once("], &[").unwrap();
+    let disps: Vec<_> = numbers(disps)
+        .chunks(2)
+        .map(|d| (d[0] as u32, d[1] as u32))
+        .collect();
+    let entries: Vec<_> = numbers(entries)
+        .chunks(2)
+        .map(|e| (e[0] as usize, e[1] as u32))
+        .collect();
+    let map2 = PhfMap::new(
+        key.parse().unwrap(),
+        Box::leak(disps.into_boxed_slice()),
+        Box::leak(entries.into_boxed_slice()),
+    );
+    assert_eq!(map2.len(), map.len());
+    for (key, value) in &map {
+        assert_eq!(map2.get(key), Some(value));
+    }
+    assert_eq!(map2.get(&1), None);
+}
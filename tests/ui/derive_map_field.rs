@@ -0,0 +1,10 @@
+use constuneval_derive::Uneval;
+use std::collections::HashMap;
+
+// `&[(k, v)]` isn't a `HashMap`
+#[derive(Uneval)]
+struct Record {
+    names: HashMap<u8, &'static str>,
+}
+
+fn main() {}
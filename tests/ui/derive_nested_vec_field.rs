@@ -0,0 +1,9 @@
+use constuneval_derive::Uneval;
+
+// `Some(&[...])` isn't an `Option<Vec<u8>>`
+#[derive(Uneval)]
+struct Record {
+    values: Option<Vec<u8>>,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use constuneval_derive::Uneval;
+
+// `&[...]` and `"..."` aren't a `Vec` and a `String`
+#[derive(Uneval)]
+struct Record {
+    name: String,
+    values: std::vec::Vec<u16>,
+}
+
+fn main() {}
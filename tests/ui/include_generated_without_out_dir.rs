@@ -0,0 +1,3 @@
+constuneval::include_generated!("tables.rs");
+
+fn main() {}
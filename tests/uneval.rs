@@ -0,0 +1,616 @@
+extern crate constuneval;
+
+use constuneval::{to_string, Emitter, Owned, Result, Uneval, UnevalCow, ViaDebug};
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl Uneval for Point {
+    fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+        e.struct_expr("Point")
+            .field("x", &self.x)
+            .field("y", &self.y)
+            .finish()
+    }
+}
+
+#[test]
+fn test_uneval_primitive() {
+    assert_eq!(to_string("A", &-7_i64, Some("i64")), "const A: i64 = -7;");
+    assert_eq!(to_string("A", &2.5_f32, Some("f32")), "const A: f32 = 2.5;");
+    assert_eq!(to_string("A", &1.0_f64, Some("f64")), "const A: f64 = 1.0;");
+    assert_eq!(to_string("A", &true, Some("bool")), "const A: bool = true;");
+    assert_eq!(
+        to_string("A", &'\n', Some("char")),
+        "const A: char = '\\n';"
+    );
+    assert_eq!(to_string("A", &(), Some("()")), "const A: () = ();");
+    assert_eq!(
+        to_string("A", &"a \"quoted\" str", Some("&str")),
+        "const A: &str = \"a \\\"quoted\\\" str\";"
+    );
+}
+
+#[test]
+fn test_uneval_float() {
+    use constuneval::Config;
+
+    let floats = [0.1, -0.0, f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 1e300];
+    assert_eq!(
+        to_string("A", &floats, None),
+        "const A: [f64; 6] = [
+    0.1,
+    -0.0,
+    f64::NAN,
+    f64::INFINITY,
+    f64::NEG_INFINITY,
+    1e300,
+];"
+    );
+    let payload = f32::from_bits(0x7fa0_0001);
+    assert_eq!(
+        to_string("A", &(payload, -f32::NAN), None),
+        "const A: (f32, f32) = (\n    f32::from_bits(0x7fa00001),\n    \
+         f32::from_bits(0xffc00000),\n);"
+    );
+    assert_eq!(
+        Config::new()
+            .float_bits(true)
+            .to_string("A", &[1.5_f32, 0.0], None),
+        "const A: [f32; 2] = [\n    f32::from_bits(0x3fc00000),\n    \
+         f32::from_bits(0x00000000),\n];"
+    );
+}
+
+#[test]
+fn test_literal_format() {
+    use constuneval::{Config, Radix};
+
+    let config = Config::new().suffixes(true);
+    assert_eq!(
+        config.to_string("A", &(1_u8, -3.5_f32, i64::MIN), None),
+        "const A: (u8, f32, i64) = (\n    1u8,\n    -3.5f32,\n    -9223372036854775808i64,\n);"
+    );
+    let cow: UnevalCow<[u16]> = UnevalCow::Owned(vec![0xbeef, 1]);
+    assert_eq!(
+        Config::new()
+            .radix(Radix::Hex)
+            .digit_group(2)
+            .to_string("A", &cow, None),
+        "const A: ::constuneval::UnevalCow<'static, [u16]> = ::constuneval::UnevalCow::Borrowed(
+    &[
+        0xbe_ef,
+        0x00_01,
+    ],
+);"
+    );
+    let config = Config::new().radix(Radix::Binary).digit_group(4);
+    assert_eq!(
+        config.to_string("A", &(-128_i8, 5_usize), None),
+        "const A: (i8, usize) = (\n    -0b1000_0000,\n    0b101,\n);"
+    );
+    assert_eq!(
+        Config::new()
+            .radix(Radix::Octal)
+            .to_string("A", &8_u8, None),
+        "const A: u8 = 0o010;"
+    );
+    assert_eq!(
+        Config::new()
+            .digit_group(3)
+            .to_string("A", &[1_000_000_u32, 100], None),
+        "const A: [u32; 2] = [\n    1_000_000,\n    100,\n];"
+    );
+}
+
+#[test]
+fn test_style() {
+    use constuneval::{Config, Style};
+
+    let config = Config::new().style(Style::Compact);
+    assert_eq!(
+        config.to_string(
+            "A",
+            &(Point { x: 1, y: 2 }, (3_u8,), Some(4_u8), [0_u8; 0]),
+            None
+        ),
+        "const A: (Point, (u8,), Option<u8>, [u8; 0]) = (Point { x: 1, y: 2 }, (3,), Some(4), []);"
+    );
+
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct Unit(u8);
+    assert_eq!(
+        Config::new().style(Style::Packed(4)).to_string(
+            "A",
+            &[ViaDebug(Unit(1))],
+            Some("[Unit; 1]")
+        ),
+        "const A: [Unit; 1] = [\n    Unit(1),\n];"
+    );
+
+    let points = [
+        Point { x: 1, y: 2 },
+        Point { x: 3, y: 4 },
+        Point { x: 5, y: 6 },
+    ];
+    assert_eq!(
+        Config::new()
+            .style(Style::Packed(2))
+            .to_string("A", &points, None),
+        "const A: [Point; 3] = [
+    Point { x: 1, y: 2 }, Point { x: 3, y: 4 },
+    Point { x: 5, y: 6 },
+];"
+    );
+
+    let cow: UnevalCow<[u32]> = UnevalCow::Owned((0..12).map(|i| i * 100).collect());
+    assert_eq!(
+        Config::new()
+            .style(Style::Wrapped(30))
+            .to_string("A", &cow, None),
+        "const A: ::constuneval::UnevalCow<'static, [u32]> = ::constuneval::UnevalCow::Borrowed(
+    &[
+        0, 100, 200, 300, 400,
+        500, 600, 700, 800,
+        900, 1000, 1100,
+    ],
+);"
+    );
+}
+
+#[test]
+fn test_uneval_compound() {
+    assert_eq!(
+        to_string("A", &(1_u8,), Some("(u8,)")),
+        "const A: (u8,) = (\n    1,\n);"
+    );
+    assert_eq!(
+        to_string("A", &[Some(1_u8), None], Some("[Option<u8>; 2]")),
+        "const A: [Option<u8>; 2] = [\n    Some(\n        1,\n    ),\n    None,\n];"
+    );
+    let slice: &[u8] = &[];
+    assert_eq!(
+        to_string("A", &slice, Some("&[u8]")),
+        "const A: &[u8] = &[];"
+    );
+    let res: Result<(), &str> = Err("bad");
+    assert_eq!(
+        to_string("A", &res, Some("Result<(), &str>")),
+        "const A: Result<(), &str> = Err(\n    \"bad\",\n);"
+    );
+}
+
+#[test]
+fn test_uneval_owned() {
+    use constuneval::try_to_string;
+
+    assert_eq!(
+        to_string("A", &vec![vec![1_u8], vec![]], None),
+        "const A: &'static [&'static [u8]] = &[\n    &[\n        1,\n    ],\n    &[],\n];"
+    );
+    assert_eq!(
+        to_string("A", &Some("a".to_string()), None),
+        "const A: Option<&'static str> = Some(\n    \"a\",\n);"
+    );
+    assert_eq!(
+        to_string("A", &(Owned(String::new()), Owned(&Vec::<u8>::new())), None),
+        "const A: (String, Vec<u8>) = (\n    String::new(),\n    Vec::new(),\n);"
+    );
+    let err = try_to_string("A", &[Owned(vec![1_u8])], None).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "A[0]: non empty `Vec` can't be created in const context"
+    );
+}
+
+#[test]
+fn test_uneval_collections() {
+    use std::collections::{BTreeSet, HashMap, HashSet};
+
+    let map: HashMap<_, _> = (0..20_u8).rev().map(|i| (i, i % 2 == 0)).collect();
+    let code = to_string("A", &map, None);
+    assert!(code.starts_with(
+        "const A: &'static [(u8, bool)] = &[\n    (\n        0,\n        true,\n    ),\n    \
+         (\n        1,"
+    ));
+    let set: HashSet<_> = map.keys().copied().collect();
+    assert_eq!(
+        to_string("A", &set, None),
+        to_string("A", &(0..20_u8).collect::<BTreeSet<_>>(), None)
+    );
+    assert_eq!(
+        to_string("A", &BTreeSet::<char>::new(), None),
+        "const A: &'static [char] = &[];"
+    );
+}
+
+#[test]
+fn test_uneval_struct() {
+    let points: &[Point] = &[Point { x: 1, y: -1 }];
+    assert_eq!(
+        to_string("A", &points, Some("&[Point]")),
+        "const A: &[Point] = &[\n    Point {\n        x: 1,\n        y: -1,\n    },\n];"
+    );
+}
+
+#[test]
+fn test_uneval_cow() {
+    use constuneval::{Config, Style};
+
+    let cow: UnevalCow<[u8]> = UnevalCow::Owned(vec![1]);
+    assert_eq!(
+        to_string("A", &cow, Some("UnevalCow<'static, [u8]>")),
+        "const A: UnevalCow<'static, [u8]> = ::constuneval::UnevalCow::Borrowed(\n    &[\n        \
+         1,\n    ],\n);"
+    );
+    let cow: UnevalCow<str> = UnevalCow::Owned("Hello".to_string());
+    assert_eq!(
+        to_string("A", &cow, Some("UnevalCow<'static, str>")),
+        "const A: UnevalCow<'static, str> = ::constuneval::UnevalCow::Borrowed(\n    \"Hello\",\n);"
+    );
+
+    let config = Config::new()
+        .style(Style::Compact)
+        .cow_path("crate::deps::Cow");
+    let nested: UnevalCow<[UnevalCow<str>]> = UnevalCow::Owned(vec![cow.clone()]);
+    assert_eq!(
+        config.to_string("A", &nested, None),
+        "const A: crate::deps::Cow<'static, [crate::deps::Cow<'static, str>]> = \
+         crate::deps::Cow::Borrowed(&[crate::deps::Cow::Borrowed(\"Hello\")]);"
+    );
+    // `Debug` doesn't depend on the configuration, the full path written is replaced by
+    // `ViaDebug`, but not in strings
+    let text: UnevalCow<str> = UnevalCow::Borrowed("::constuneval::UnevalCow::Borrowed(");
+    assert_eq!(
+        config.to_string(
+            "A",
+            &ViaDebug((cow.clone(), text.clone(), '"')),
+            Some("(Cow<str>, Cow<str>, char)")
+        ),
+        "const A: (Cow<str>, Cow<str>, char) = (crate::deps::Cow::Borrowed( \"Hello\" ), \
+         crate::deps::Cow::Borrowed( \"::constuneval::UnevalCow::Borrowed(\" ), '\"');"
+    );
+    assert_eq!(
+        config.clone().cow_path("deps::Cow").module("m").to_string(
+            "A",
+            &ViaDebug(Some(cow.clone())),
+            Some("Option<deps::Cow<str>>")
+        ),
+        "pub mod m {\npub(super) const A: Option<deps::Cow<str>> = \
+         Some(deps::Cow::Borrowed( \"Hello\" ));\nuse super::deps;\n}"
+    );
+    // only whole paths are replaced in types
+    assert_eq!(
+        config.to_string(
+            "A",
+            &cow,
+            Some("::other::constuneval::UnevalCow<'static, str>")
+        ),
+        "const A: ::other::constuneval::UnevalCow<'static, str> = \
+         crate::deps::Cow::Borrowed(\"Hello\");"
+    );
+    assert_eq!(
+        config.to_string("A", &0_u8, Some("my_crate::constuneval::Table")),
+        "const A: my_crate::constuneval::Table = 0;"
+    );
+    assert_eq!(
+        format!("{:?}", cow),
+        "::constuneval::UnevalCow::Borrowed( \"Hello\" )"
+    );
+    assert_eq!(
+        format!("{:#?}", cow),
+        "::constuneval::UnevalCow::Borrowed( \"Hello\" )"
+    );
+    assert_eq!(
+        config
+            .clone()
+            .cow_path("UnevalCow")
+            .module("m")
+            .to_string("A", &cow, None),
+        "pub mod m {\npub(super) const A: UnevalCow<'static, str> = \
+         UnevalCow::Borrowed(\"Hello\");\nuse super::UnevalCow;\n}"
+    );
+    // relative paths are imported from the scope including the module
+    let nested: UnevalCow<[u8]> = UnevalCow::Owned(vec![]);
+    let config = config.cow_path("self::deps::Cow").module("m");
+    assert_eq!(
+        config.to_string("A", &nested, None),
+        "pub mod m {\npub(super) const A: self::deps::Cow<'static, [u8]> = \
+         self::deps::Cow::Borrowed(&[]);\nuse super::deps;\n}"
+    );
+    // including when they only appear in the type
+    let empty: Vec<UnevalCow<str>> = vec![];
+    assert_eq!(
+        config.to_string("A", &empty, None),
+        "pub mod m {\npub(super) const A: &'static [self::deps::Cow<'static, str>] = &[];\n\
+         use super::deps;\n}"
+    );
+}
+
+/// Types emitted with `ViaDebug` in a module.
+mod shapes {
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    pub struct Corner {
+        pub x: i32,
+    }
+
+    pub mod other {
+        #[derive(Debug)]
+        pub struct Corner;
+    }
+}
+
+#[test]
+fn test_via_debug_module() {
+    use constuneval::{Config, Style};
+    use shapes::{other, Corner};
+
+    let config = Config::new().module("m").style(Style::Compact);
+    // the generic arguments of the type are imported
+    assert_eq!(
+        config.to_string("A", &ViaDebug(Some(Corner { x: 1 })), None),
+        "pub mod m {\npub(super) const A: Option<Corner> = Some(Corner { x: 1 });\n\
+         use ::uneval::shapes::Corner;\n}"
+    );
+    assert_eq!(
+        config.to_string(
+            "A",
+            &ViaDebug(Some(Corner { x: 1 })),
+            Some("Option<Corner>")
+        ),
+        "pub mod m {\npub(super) const A: Option<Corner> = Some(Corner { x: 1 });\n\
+         use ::uneval::shapes::Corner;\n}"
+    );
+    // including the ones only appearing in the item type
+    let empty: Vec<ViaDebug<Corner>> = Vec::new();
+    assert_eq!(
+        config.to_string("A", &empty, None),
+        "pub mod m {\npub(super) const A: &'static [Corner] = &[];\n\
+         use ::uneval::shapes::Corner;\n}"
+    );
+    // but not the ones the item type doesn't name
+    assert_eq!(
+        config.to_string("A", &empty, Some("&'static [shapes::Corner]")),
+        "pub mod m {\npub(super) const A: &'static [shapes::Corner] = &[];\n}"
+    );
+
+    let err = config
+        .try_to_string("A", &ViaDebug((Corner { x: 1 }, other::Corner)), None)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "m: `::uneval::shapes::Corner` and `::uneval::shapes::other::Corner` can't both be \
+         imported"
+    );
+}
+
+#[test]
+fn test_via_debug() {
+    #[derive(Debug)]
+    struct Unit;
+    assert_eq!(
+        to_string("A", &Some(ViaDebug(Unit)), Some("Option<Unit>")),
+        "const A: Option<Unit> = Some(\n    Unit,\n);"
+    );
+
+    // `UnevalCow` keeps the pretty layout of its content
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct Table {
+        rows: UnevalCow<'static, [u8]>,
+    }
+    let table = Table {
+        rows: UnevalCow::Owned(vec![1, 2]),
+    };
+    assert_eq!(
+        to_string("A", &ViaDebug(table), Some("Table")),
+        "const A: Table = Table {
+    rows: ::constuneval::UnevalCow::Borrowed( &[
+        1,
+        2,
+    ] ),
+};"
+    );
+}
+
+#[test]
+fn test_item_kind() {
+    use constuneval::{Config, ItemKind, Visibility};
+
+    let config = Config::new().kind(ItemKind::Static);
+    assert_eq!(
+        config.to_string("A", &1_u8, Some("u8")),
+        "static A: u8 = 1;"
+    );
+    assert_eq!(
+        config
+            .vis(Visibility::Public)
+            .to_string("A", &1_u8, Some("u8")),
+        "pub static A: u8 = 1;"
+    );
+    assert_eq!(
+        Config::new()
+            .vis(Visibility::Public)
+            .to_string("A", &1_u8, Some("u8")),
+        "pub const A: u8 = 1;"
+    );
+}
+
+#[test]
+fn test_item_attributes() {
+    use constuneval::{Config, Visibility};
+
+    assert_eq!(
+        Config::new()
+            .vis(Visibility::Super)
+            .to_string("A", &1_u8, None),
+        "pub(super) const A: u8 = 1;"
+    );
+    let config = Config::new()
+        .vis(Visibility::restricted("crate::tables").unwrap())
+        .doc("Lookup table.\n\nGenerated by `build.rs`.")
+        .attr("rustfmt::skip")
+        .attr("cfg(feature = \"tables\")");
+    assert_eq!(
+        config.to_string("A", &1_u8, None),
+        "/// Lookup table.
+///
+/// Generated by `build.rs`.
+#[rustfmt::skip]
+#[cfg(feature = \"tables\")]
+pub(in crate::tables) const A: u8 = 1;"
+    );
+
+    // paths are checked when the visibility is built, and can be computed
+    let module = format!("super::{}", "tables");
+    assert_eq!(
+        Config::new()
+            .vis(Visibility::restricted(module).unwrap())
+            .to_string("A", &1_u8, None),
+        "pub(in super::tables) const A: u8 = 1;"
+    );
+    assert!(Visibility::restricted("super::super").is_ok());
+    for path in [
+        "tables",
+        "::crate",
+        "crate::super",
+        "self::",
+        "crate::a b",
+        "crate::_",
+    ] {
+        assert_eq!(
+            Visibility::restricted(path).unwrap_err().to_string(),
+            format!(
+                "`{}` is not a `pub(in ...)` path starting with `crate`, `self` or `super`",
+                path
+            )
+        );
+    }
+}
+
+#[test]
+fn test_errors() {
+    use constuneval::{try_to_string, Config, Error, ItemKind};
+
+    struct Table {
+        rows: Vec<(u8, Owned<String>)>,
+    }
+
+    impl Uneval for Table {
+        fn uneval(&self, e: &mut Emitter<'_>) -> Result<()> {
+            e.struct_expr("Table")
+                .field("rows", &self.rows.as_slice())
+                .finish()
+        }
+    }
+
+    let table = Table {
+        rows: vec![(1, Owned(String::new())), (2, Owned("a".to_string()))],
+    };
+    let err = try_to_string("TABLE", &table, Some("Table")).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "TABLE.rows[1].1: non empty `String` can't be created in const context"
+    );
+
+    for name in &["", "1A", "A-B", "const", "static _"] {
+        match try_to_string(name, &1_u8, Some("u8")) {
+            Err(Error::InvalidName(n)) => assert_eq!(&n, name),
+            _ => panic!("`{}` should be invalid", name),
+        }
+    }
+    // strict, reserved and edition-dependent keywords
+    for name in &["struct", "dyn", "abstract", "box", "try", "yield", "gen"] {
+        assert!(try_to_string(name, &1_u8, Some("u8")).is_err(), "{}", name);
+        let raw = format!("r#{}", name);
+        assert!(try_to_string(&raw, &1_u8, Some("u8")).is_ok(), "{}", raw);
+    }
+    // path segments can't be raw identifiers
+    for name in &["r#crate", "r#self", "r#super", "r#Self", "r#_"] {
+        assert!(try_to_string(name, &1_u8, Some("u8")).is_err(), "{}", name);
+    }
+    assert!(try_to_string("r#const", &1_u8, Some("u8")).is_ok());
+    assert!(try_to_string("_", &1_u8, Some("u8")).is_ok());
+    assert!(Config::new()
+        .kind(ItemKind::Static)
+        .try_to_string("_", &1_u8, Some("u8"))
+        .is_err());
+
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct Private {
+        field: u8,
+    }
+
+    impl std::fmt::Debug for Table {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("Table").finish_non_exhaustive()
+        }
+    }
+
+    let err = try_to_string("A", &ViaDebug(table), Some("Table")).unwrap_err();
+    assert_eq!(err.to_string(), "A: Debug output has non exhaustive fields");
+    assert!(try_to_string("A", &ViaDebug(Private { field: 1 }), Some("Private")).is_ok());
+}
+
+#[test]
+fn test_type_inference() {
+    assert_eq!(to_string("A", &1_u8, None), "const A: u8 = 1;");
+    assert_eq!(to_string("A", &"a", None), "const A: &'static str = \"a\";");
+    let slice: &[Option<(u8,)>] = &[];
+    assert_eq!(
+        to_string("A", &slice, None),
+        "const A: &'static [Option<(u8,)>] = &[];"
+    );
+    assert_eq!(
+        to_string("A", &[(1_u8, 'a')], None),
+        "const A: [(u8, char); 1] = [\n    (\n        1,\n        'a',\n    ),\n];"
+    );
+    let cow: UnevalCow<[UnevalCow<str>]> = UnevalCow::Owned(vec![]);
+    assert_eq!(
+        to_string("A", &cow, None),
+        "const A: ::constuneval::UnevalCow<'static, [::constuneval::UnevalCow<'static, str>]> = \
+         ::constuneval::UnevalCow::Borrowed(\n    &[],\n);"
+    );
+    assert_eq!(
+        to_string("A", &Point { x: 1, y: 2 }, None),
+        "const A: Point = Point {\n    x: 1,\n    y: 2,\n};"
+    );
+    assert_eq!(
+        <ViaDebug<Option<std::time::Duration>>>::const_type().unwrap(),
+        "Option<core::time::Duration>"
+    );
+}
+
+#[test]
+fn test_uneval_bytes() {
+    use constuneval::{Config, Radix};
+
+    let sbox: Vec<u8> = vec![0x63, 0x7c, 0x77];
+    let config = Config::new().radix(Radix::Hex).suffixes(true);
+    assert_eq!(
+        config.to_string("SBOX", &sbox, None),
+        "const SBOX: &'static [u8] = &[\n    0x63u8,\n    0x7cu8,\n    0x77u8,\n];"
+    );
+    assert_eq!(
+        config
+            .clone()
+            .byte_strings(3)
+            .to_string("SBOX", &sbox, None),
+        "const SBOX: &'static [u8] = b\"c|w\";"
+    );
+    // byte strings are the default from 16 bytes
+    let data = b"0123456789abcdef".to_vec();
+    assert_eq!(
+        to_string("DATA", &data, None),
+        "const DATA: &'static [u8] = b\"0123456789abcdef\";"
+    );
+    let code = config
+        .byte_strings(usize::MAX)
+        .to_string("DATA", &data, None);
+    assert!(code.starts_with("const DATA: &'static [u8] = &[\n    0x30u8,\n"));
+}
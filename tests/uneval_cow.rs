@@ -67,31 +67,31 @@ fn test_debug_primitive() {
     // int
     assert_eq!(
         format!("{:?}", UnevalCow::<u64>::Borrowed(&1)),
-        "UnevalCow::Borrowed( &1 )"
+        "::constuneval::UnevalCow::Borrowed( &1 )"
     );
     assert_eq!(
         format!("{:?}", UnevalCow::<u64>::Owned(1)),
-        "UnevalCow::Borrowed( &1 )"
+        "::constuneval::UnevalCow::Borrowed( &1 )"
     );
     // float
     assert_eq!(
         format!("{:?}", UnevalCow::<f64>::Borrowed(&3.7_f64)),
-        "UnevalCow::Borrowed( &3.7 )"
+        "::constuneval::UnevalCow::Borrowed( &3.7 )"
     );
     assert_eq!(
         format!("{:?}", UnevalCow::<f64>::Owned(3.7_f64)),
-        "UnevalCow::Borrowed( &3.7 )"
+        "::constuneval::UnevalCow::Borrowed( &3.7 )"
     );
 
     // str
     assert_eq!(
         format!("{:?}", UnevalCow::<str>::Borrowed("Hello")),
-        "UnevalCow::Borrowed( \"Hello\" )"
+        "::constuneval::UnevalCow::Borrowed( \"Hello\" )"
     );
 
     assert_eq!(
         format!("{:?}", UnevalCow::<str>::Owned("Hello".to_string())),
-        "UnevalCow::Borrowed( \"Hello\" )"
+        "::constuneval::UnevalCow::Borrowed( \"Hello\" )"
     );
 }
 
@@ -100,22 +100,44 @@ fn test_debug_slice() {
     // [T]
     assert_eq!(
         format!("{:?}", UnevalCow::<[u64]>::Borrowed(&[1, 2, 3])),
-        "UnevalCow::Borrowed( &[1, 2, 3] )"
+        "::constuneval::UnevalCow::Borrowed( &[1, 2, 3] )"
     );
 
     assert_eq!(
         format!("{:?}", UnevalCow::<[u64]>::Owned(vec![1, 2, 3])),
-        "UnevalCow::Borrowed( &[1, 2, 3] )"
+        "::constuneval::UnevalCow::Borrowed( &[1, 2, 3] )"
     );
 
     // Box<[T]>
     let box_u64: Box<[u64]> = Box::new([1, 2, 3]);
     assert_eq!(
         format!("{:?}", UnevalCow::<Box<[u64]>>::Borrowed(&box_u64)),
-        "UnevalCow::Borrowed( &[1, 2, 3] )"
+        "::constuneval::UnevalCow::Borrowed( &[1, 2, 3] )"
     );
     assert_eq!(
         format!("{:?}", UnevalCow::<Box<[u64]>>::Owned(box_u64)),
-        "UnevalCow::Borrowed( &[1, 2, 3] )"
+        "::constuneval::UnevalCow::Borrowed( &[1, 2, 3] )"
+    );
+}
+
+#[test]
+fn test_debug_flags() {
+    let nested: UnevalCow<[UnevalCow<[u8]>]> = UnevalCow::Owned(vec![UnevalCow::Owned(vec![1, 2])]);
+    assert_eq!(
+        format!("{:#?}", nested),
+        "::constuneval::UnevalCow::Borrowed( &[
+    ::constuneval::UnevalCow::Borrowed( &[
+        1,
+        2,
+    ] ),
+] )"
+    );
+    assert_eq!(
+        format!("{:.2?}", UnevalCow::<[f64]>::Owned(vec![0.5, 1.0])),
+        "::constuneval::UnevalCow::Borrowed( &[0.50, 1.00] )"
+    );
+    assert_eq!(
+        format!("{:5?}", UnevalCow::<u8>::Owned(7)),
+        "::constuneval::UnevalCow::Borrowed( &    7 )"
     );
 }
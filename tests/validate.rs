@@ -0,0 +1,46 @@
+#![cfg(feature = "validate")]
+extern crate constuneval;
+
+use constuneval::{to_file, validate, ConstWriter, Error, ViaDebug};
+use std::collections::HashMap;
+
+#[test]
+fn test_validate_error() {
+    let err = validate("const A: u8 = 1;\nconst B: [u8; 2] = [1 2];").unwrap_err();
+    assert_eq!((err.line(), err.column()), (2, 23));
+    assert_eq!(err.snippet(), "const B: [u8; 2] = [1 2];");
+    assert_eq!(
+        err.to_string(),
+        "generated code is not valid Rust: expected `,` or `;` (line 2, column 23)
+const B: [u8; 2] = [1 2];
+                      ^"
+    );
+
+    let err = validate("const A: u8 = ").unwrap_err();
+    assert_eq!((err.line(), err.column()), (1, 15));
+}
+
+#[test]
+fn test_validate_to_file() {
+    let map: HashMap<u8, u8> = vec![(1, 2)].into_iter().collect();
+    let target = std::env::temp_dir().join("constuneval_test_validate_to_file.rs");
+    match to_file(&target, "MAP", &ViaDebug(map), Some("Map")) {
+        Err(Error::Validation(err)) => assert_eq!(err.snippet(), "    1: 2,"),
+        _ => unreachable!(),
+    }
+    assert!(!target.exists());
+
+    let err = ConstWriter::new(&target)
+        .raw("fn broken(")
+        .write()
+        .unwrap_err();
+    assert!(matches!(err, Error::Validation(_)));
+    assert!(!target.exists());
+}
+
+#[test]
+#[should_panic(expected = "generated code is not valid Rust")]
+fn test_validate_to_string() {
+    let map: HashMap<u8, u8> = vec![(1, 2)].into_iter().collect();
+    constuneval::to_string("MAP", &ViaDebug(map), Some("Map"));
+}
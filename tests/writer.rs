@@ -0,0 +1,440 @@
+extern crate constuneval;
+
+use constuneval::{Config, ConstWriter, Error, Header, ItemKind, Style, UnevalCow, ViaDebug};
+use std::path::Path;
+
+/// Content of a generated file after its header, which must be valid.
+fn read_generated(path: &Path) -> String {
+    let code = std::fs::read_to_string(path).unwrap();
+    assert!(Header::verify(&code), "invalid header in {:?}", code);
+    code.split_once("\n\n").unwrap().1.to_string()
+}
+
+#[test]
+fn test_writer_render() {
+    let table: &[u8] = &[1, 2];
+    let writer = ConstWriter::new("unused.rs")
+        .use_item("std::num::Wrapping")
+        .type_alias("Table", "&'static [u8]")
+        .item("TABLE", &table, Some("Table"))
+        .item_with(
+            Config::new().kind(ItemKind::Static),
+            "ONE",
+            &1_u8,
+            Some("u8"),
+        )
+        .raw("const TWO: Wrapping<u8> = Wrapping(2);\n")
+        .header(Header::none());
+    assert_eq!(
+        writer.render().unwrap(),
+        "use std::num::Wrapping;
+type Table = &'static [u8];
+const TABLE: Table = &[
+    1,
+    2,
+];
+static ONE: u8 = 1;
+const TWO: Wrapping<u8> = Wrapping(2);
+"
+    );
+}
+
+#[test]
+fn test_writer_duplicates() {
+    let writer = ConstWriter::new("unused.rs")
+        .type_alias("A", "u8")
+        .item("A", &1_u8, Some("u8"))
+        .item("B", &1_u8, Some("u8"))
+        .item("A", &2_u8, Some("u8"))
+        .type_alias("A", "u8");
+    let err = writer.render().unwrap_err();
+    assert_eq!(err.to_string(), "duplicate item names: `A`, `A`");
+    match err {
+        Error::DuplicateNames(names) => assert_eq!(names, ["A", "A"]),
+        _ => unreachable!(),
+    }
+    let code = ConstWriter::new("unused.rs")
+        .header(Header::none())
+        .item("_", &1_u8, Some("u8"))
+        .item("_", &2_u8, Some("u8"))
+        .render()
+        .unwrap();
+    assert_eq!(code, "const _: u8 = 1;\nconst _: u8 = 2;\n");
+}
+
+#[test]
+fn test_writer_file_options() {
+    use constuneval::{Header, WriteMode};
+
+    let configs = [
+        (Config::new().module("tables"), "module"),
+        (Config::new().header(Header::none()), "header"),
+        (Config::new().write_mode(WriteMode::IfChanged), "write_mode"),
+    ];
+    for (config, option) in configs {
+        let err = ConstWriter::new("unused.rs")
+            .item("A", &1_u8, None)
+            .item_with(config.clone(), "B", &1_u8, None)
+            .render()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "B: `{}` applies to the whole file, set it on the ConstWriter",
+                option
+            )
+        );
+        let err = ConstWriter::new("unused.rs")
+            .config(config)
+            .item("A", &1_u8, None)
+            .write()
+            .unwrap_err();
+        assert!(matches!(err, Error::FileOption { item, .. } if item == "A"));
+    }
+}
+
+#[test]
+fn test_writer_write() {
+    let target = std::env::temp_dir().join("constuneval_test_writer_write.rs");
+    ConstWriter::new(&target)
+        .item("A", &1_u8, Some("u8"))
+        .item("B", &2_u8, Some("u8"))
+        .write()
+        .unwrap();
+    assert_eq!(
+        read_generated(&target),
+        "const A: u8 = 1;\nconst B: u8 = 2;\n"
+    );
+    std::fs::remove_file(target).unwrap();
+}
+
+#[test]
+fn test_write_if_changed() {
+    use constuneval::WriteMode;
+    use std::fs;
+    #[cfg(unix)]
+    use std::os::unix::fs::MetadataExt;
+    use std::time::{Duration, SystemTime};
+
+    let dir = std::env::temp_dir().join("constuneval_test_write_if_changed");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir(&dir).unwrap();
+    let target = dir.join("table.rs");
+    let config = Config::new().write_mode(WriteMode::IfChanged);
+
+    config.to_file(&target, "A", &1_u8, Some("u8")).unwrap();
+    // an old modification time, which any rewrite of the file would update
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    fs::File::options()
+        .write(true)
+        .open(&target)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+    #[cfg(unix)]
+    let inode = fs::metadata(&target).unwrap().ino();
+
+    config.to_file(&target, "A", &1_u8, Some("u8")).unwrap();
+    assert_eq!(fs::metadata(&target).unwrap().modified().unwrap(), modified);
+    #[cfg(unix)]
+    assert_eq!(fs::metadata(&target).unwrap().ino(), inode);
+    assert_eq!(read_generated(&target), "const A: u8 = 1;\n");
+
+    config.to_file(&target, "A", &2_u8, Some("u8")).unwrap();
+    assert_ne!(fs::metadata(&target).unwrap().modified().unwrap(), modified);
+    assert_eq!(read_generated(&target), "const A: u8 = 2;\n");
+
+    // no temporary file is left behind
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_writer_sidecar() {
+    use constuneval::Aligned;
+    use std::fs;
+
+    // names of the files included by `code`, which depend on their content
+    fn sidecars(code: &str) -> Vec<&str> {
+        code.split("include_bytes!(\"")
+            .skip(1)
+            .map(|rest| &rest[..rest.find('"').unwrap()])
+            .collect()
+    }
+
+    let dir = std::env::temp_dir().join("constuneval_test_writer_sidecar");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir(&dir).unwrap();
+    // files of the user are never removed, even with a sidecar-like name
+    fs::write(dir.join("data.0.bin"), b"user").unwrap();
+    let target = dir.join("data.rs");
+    let big = vec![7_u8; 64];
+    let aligned: Aligned<u64, _> = Aligned::new(big.clone());
+    ConstWriter::new(&target)
+        .config(Config::new().sidecar_threshold(16).byte_strings(4))
+        .item("SMALL", &b"\"quoted\"\n".to_vec(), None)
+        .item("BIG", &big, None)
+        .item("ALIGNED", &aligned, None)
+        .write()
+        .unwrap();
+    let code = read_generated(&target);
+    let names = sidecars(&code);
+    assert_eq!(names.len(), 2);
+    let big_name = names[0].to_string();
+    assert_eq!(names[1], big_name);
+    assert!(big_name.starts_with("data.") && big_name.ends_with(".bin"));
+    assert_eq!(big_name.len(), "data.0123456789abcdef.bin".len());
+    assert_eq!(
+        code.replace(&big_name, "big.bin"),
+        "const SMALL: &'static [u8] = b\"\\\"quoted\\\"\\n\";
+const BIG: &'static [u8] = include_bytes!(\"big.bin\");
+const ALIGNED: &'static ::constuneval::Aligned<u64, [u8]> = \
+&::constuneval::Aligned::new(*include_bytes!(\"big.bin\"));
+"
+    );
+    assert_eq!(fs::read(dir.join(&big_name)).unwrap(), big);
+    assert_eq!(
+        fs::read_to_string(dir.join("data.rs.sidecars")).unwrap(),
+        format!("{}\n", big_name)
+    );
+
+    // files of the previous run are kept while they are included
+    let other = vec![8_u8; 32];
+    let config = Config::new().sidecar_threshold(16);
+    let both = (other.clone(), big.clone(), other.clone());
+    config.to_file(&target, "BOTH", &both, None).unwrap();
+    let code = read_generated(&target);
+    let names = sidecars(&code);
+    assert_eq!(names[1], big_name);
+    assert_eq!(names[0], names[2]);
+    let other_name = names[0].to_string();
+    assert_eq!(fs::read(dir.join(&big_name)).unwrap(), big);
+    assert_eq!(fs::read(dir.join(&other_name)).unwrap(), other);
+
+    // and removed once they aren't
+    config.to_file(&target, "OTHER", &other, None).unwrap();
+    assert!(!dir.join(&big_name).exists());
+    assert_eq!(fs::read(dir.join(&other_name)).unwrap(), other);
+    Config::new().to_file(&target, "NONE", &0_u8, None).unwrap();
+
+    // files written before an error are removed with the temporary file
+    struct Opaque;
+
+    impl std::fmt::Debug for Opaque {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("Opaque").finish_non_exhaustive()
+        }
+    }
+
+    let failing = (big.clone(), ViaDebug(Opaque));
+    config
+        .to_file(&target, "FAILING", &failing, None)
+        .unwrap_err();
+    ConstWriter::new(&target)
+        .config(config.clone())
+        .item("BIG", &big, None)
+        .item("OPAQUE", &ViaDebug(Opaque), Some("Opaque"))
+        .write()
+        .unwrap_err();
+    assert_eq!(read_generated(&target), "const NONE: u8 = 0;\n");
+    let mut names: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["data.0.bin", "data.rs"]);
+    assert_eq!(fs::read(dir.join("data.0.bin")).unwrap(), b"user");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_to_writer() {
+    use std::io;
+
+    let mut out = Vec::new();
+    constuneval::to_writer(&mut out, "A", &(1_u8, "a"), None).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "const A: (u8, &'static str) = (\n    1,\n    \"a\",\n);\n"
+    );
+
+    struct Full;
+
+    impl io::Write for Full {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::StorageFull, "disk full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let table: Vec<u32> = (0..10_000).collect();
+    match constuneval::to_writer(Full, "A", &table, None) {
+        Err(Error::Io(err)) => assert_eq!(err.kind(), io::ErrorKind::StorageFull),
+        res => panic!("unexpected result {:?}", res),
+    }
+}
+
+#[test]
+fn test_to_file_iter() {
+    use std::fs;
+
+    let dir = std::env::temp_dir().join("constuneval_test_to_file_iter");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir(&dir).unwrap();
+    let target = dir.join("table.rs");
+
+    constuneval::to_file_iter(&target, "SQUARES", (1..4_u32).map(|i| i * i), None).unwrap();
+    assert_eq!(
+        read_generated(&target),
+        "const SQUARES: &'static [u32] = &[\n    1,\n    4,\n    9,\n];\n"
+    );
+    constuneval::to_file_array(&target, "EVEN", (0..6_u16).step_by(2), Some("Even")).unwrap();
+    assert_eq!(
+        read_generated(&target),
+        "const EVEN: [Even; 3] = [\n    0,\n    2,\n    4,\n];\n"
+    );
+    // the length of the iterator doesn't have to be known
+    let words = ["a", "bb", "c", "dd"]
+        .iter()
+        .filter(|word| word.len() == 1)
+        .map(|word| UnevalCow::<str>::Borrowed(word));
+    Config::new()
+        .style(Style::Compact)
+        .module("m")
+        .cow_path("deps::Cow")
+        .to_file_array(&target, "WORDS", words, None)
+        .unwrap();
+    assert_eq!(
+        read_generated(&target),
+        "pub mod m {\npub(super) const WORDS: [deps::Cow<'static, str>; 2] = \
+         [deps::Cow::Borrowed(\"a\"), deps::Cow::Borrowed(\"c\")];\nuse super::deps;\n}\n"
+    );
+
+    struct Opaque;
+
+    impl std::fmt::Debug for Opaque {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("Opaque").finish_non_exhaustive()
+        }
+    }
+
+    let opaque = (0..2).map(|_| ViaDebug(Opaque));
+    let err = constuneval::to_file_array(&target, "OPAQUE", opaque, Some("Opaque")).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "OPAQUE[0]: Debug output has non exhaustive fields"
+    );
+    // the previous content is kept, and the temporary file removed
+    assert!(read_generated(&target).starts_with("pub mod m"));
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_header() {
+    use std::fs;
+
+    let writer = ConstWriter::new("unused.rs")
+        .header(Header::new().note("Regenerate with `cargo build`.\n\nSee build.rs."))
+        .item("A", &1_u8, None);
+    let code = writer.render().unwrap();
+    let (header, body) = code.split_once("\n\n").unwrap();
+    let lines: Vec<_> = header.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert!(lines[0].starts_with(concat!(
+        "// @generated by constuneval ",
+        env!("CARGO_PKG_VERSION")
+    )));
+    assert!(lines[0].ends_with(", do not edit."));
+    assert_eq!(
+        lines[1..4],
+        [
+            "// Regenerate with `cargo build`.",
+            "//",
+            "// See build.rs."
+        ]
+    );
+    assert!(lines[4].starts_with("// content-hash: fnv1a64:"));
+    assert_eq!(body, "const A: u8 = 1;\n");
+    assert!(Header::verify(&code));
+    assert!(!Header::verify(&code.replace("= 1", "= 2")));
+    assert!(!Header::verify(body));
+
+    // the streamed file is the same as the rendered one
+    let target = std::env::temp_dir().join("constuneval_test_header.rs");
+    let writer = ConstWriter::new(&target)
+        .header(Header::new().note("Regenerate with `cargo build`.\n\nSee build.rs."))
+        .item("A", &1_u8, None);
+    writer.write().unwrap();
+    assert_eq!(fs::read_to_string(&target).unwrap(), code);
+    // and a single item is written as by a writer, header hash included
+    Config::new()
+        .header(Header::new().note("Regenerate with `cargo build`.\n\nSee build.rs."))
+        .to_file(&target, "A", &1_u8, None)
+        .unwrap();
+    assert_eq!(fs::read_to_string(&target).unwrap(), code);
+
+    Config::new()
+        .header(Header::none())
+        .to_file(&target, "A", &1_u8, None)
+        .unwrap();
+    assert_eq!(fs::read_to_string(&target).unwrap(), "const A: u8 = 1;\n");
+    fs::remove_file(target).unwrap();
+}
+
+#[test]
+fn test_writer_module() {
+    use constuneval::{Aligned, ConstMap, Visibility};
+
+    let entries = [(1_u8, 2_u8)];
+    let map = ConstMap::new(&entries);
+    let aligned = Some(vec![Aligned::<u16, _>::new([0_u8; 2])]);
+    let restricted = |path| Config::new().vis(Visibility::restricted(path).unwrap());
+    let writer = ConstWriter::new("unused.rs")
+        .header(Header::none())
+        .module("tables")
+        .config(Config::new().crate_path("deps"))
+        .use_item("super::deps")
+        .item("A", &map, None)
+        .item_with(Config::new().vis(Visibility::Super), "B", &1_u8, None)
+        .item_with(Config::new().vis(Visibility::Public), "C", &map, None)
+        .item("D", &aligned, None)
+        .item_with(restricted("self"), "E", &1_u8, None)
+        .item_with(restricted("super"), "F", &1_u8, None)
+        .item_with(restricted("crate::a"), "G", &1_u8, None);
+    let code = writer.render().unwrap();
+    let lines: Vec<_> = code.lines().filter(|line| !line.starts_with(' ')).collect();
+    assert_eq!(
+        lines,
+        [
+            "pub mod tables {",
+            "use super::deps;",
+            "pub(super) const A: deps::ConstMap<'static, u8, u8> = deps::ConstMap::new(",
+            ");",
+            "pub(in super::super) const B: u8 = 1;",
+            "pub const C: ::constuneval::ConstMap<'static, u8, u8> = \
+             ::constuneval::ConstMap::new(",
+            ");",
+            "pub(super) const D: Option<&'static [&'static deps::Aligned<u16, [u8]>]> = Some(",
+            ");",
+            "pub(super) const E: u8 = 1;",
+            "pub(in super::super) const F: u8 = 1;",
+            "pub(in crate::a) const G: u8 = 1;",
+            "}",
+        ]
+    );
+
+    for name in ["_", "mod", "a-b"] {
+        let err = ConstWriter::new("unused.rs")
+            .module(name)
+            .render()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("`{}` is not a valid item name", name)
+        );
+    }
+}